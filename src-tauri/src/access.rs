@@ -0,0 +1,182 @@
+use crate::error::HousecatError;
+use crate::{fetch_rows, quote_string_literal, require_value, ClickHouseConnectionInput};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TableAccessInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseGrantRow {
+    access_type: String,
+    table: Option<String>,
+    partial_revoke: u8,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TableAccess {
+    select: bool,
+    insert: bool,
+    alter: bool,
+    drop: bool,
+}
+
+/// Whether a grant's access type covers `privilege`. Any `ALTER ...`
+/// sub-privilege counts as `ALTER`, since the UI only needs to know whether
+/// some alteration is possible.
+fn covers(access_type: &str, privilege: &str) -> bool {
+    access_type == "ALL"
+        || access_type == privilege
+        || (privilege == "ALTER" && access_type.starts_with("ALTER"))
+        || (privilege == "DROP" && access_type == "DROP TABLE")
+}
+
+fn has_privilege(grants: &[ClickHouseGrantRow], privilege: &str) -> bool {
+    let granted = grants
+        .iter()
+        .any(|grant| grant.partial_revoke == 0 && covers(&grant.access_type, privilege));
+
+    // A partial revoke only wins when it targets this table specifically;
+    // database-wide revokes alongside table grants are rare enough to skip.
+    let revoked = grants.iter().any(|grant| {
+        grant.partial_revoke == 1 && grant.table.is_some() && covers(&grant.access_type, privilege)
+    });
+
+    granted && !revoked
+}
+
+/// Checks the current user's SELECT/INSERT/ALTER/DROP rights on a table from
+/// `system.grants`, covering grants to the user and to its enabled roles.
+/// This is meant for enabling UI actions, not as an authoritative check;
+/// ClickHouse still enforces access on every statement.
+#[tauri::command]
+pub(crate) async fn fetch_table_access(
+    input: TableAccessInput,
+) -> Result<TableAccess, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+
+    let query = format!(
+        "SELECT toString(access_type) AS access_type, table, partial_revoke FROM system.grants WHERE (user_name = currentUser() OR role_name IN (SELECT role_name FROM system.enabled_roles)) AND (database IS NULL OR database = {}) AND (table IS NULL OR table = {}) FORMAT JSON",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+
+    let grants: Vec<ClickHouseGrantRow> = fetch_rows(&input.connection, query).await?;
+
+    Ok(TableAccess {
+        select: has_privilege(&grants, "SELECT"),
+        insert: has_privilege(&grants, "INSERT"),
+        alter: has_privilege(&grants, "ALTER"),
+        drop: has_privilege(&grants, "DROP"),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseSettingsProfileRow {
+    name: String,
+    storage: String,
+    apply_to_all: u8,
+    apply_to_list: Vec<String>,
+    apply_to_except: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseProfileElementRow {
+    profile_name: String,
+    setting_name: Option<String>,
+    value: Option<String>,
+    min: Option<String>,
+    max: Option<String>,
+    writability: Option<String>,
+    inherit_profile: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProfileSetting {
+    name: String,
+    value: Option<String>,
+    min: Option<String>,
+    max: Option<String>,
+    /// `WRITABLE`, `CONST` or `CHANGEABLE_IN_READONLY`, when constrained.
+    writability: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SettingsProfile {
+    name: String,
+    storage: String,
+    apply_to_all: bool,
+    apply_to_list: Vec<String>,
+    apply_to_except: Vec<String>,
+    /// Profiles this one inherits from, in the order they are applied.
+    inherits: Vec<String>,
+    settings: Vec<ProfileSetting>,
+}
+
+/// Lists the settings profiles with the settings, constraints and parent
+/// profiles each one applies. Both system tables need `SHOW PROFILES`,
+/// which is reported as [`HousecatError::AccessDenied`] when missing.
+#[tauri::command]
+pub(crate) async fn fetch_settings_profiles(
+    input: ClickHouseConnectionInput,
+) -> Result<Vec<SettingsProfile>, HousecatError> {
+    let profiles: Vec<ClickHouseSettingsProfileRow> = fetch_rows(
+        &input,
+        "SELECT name, storage, apply_to_all, apply_to_list, apply_to_except FROM system.settings_profiles ORDER BY name FORMAT JSON"
+            .to_string(),
+    )
+    .await
+    .map_err(|err| err.access_denied_as("system.settings_profiles"))?;
+
+    let elements: Vec<ClickHouseProfileElementRow> = fetch_rows(
+        &input,
+        "SELECT profile_name, setting_name, value, min, max, toString(writability) AS writability, inherit_profile FROM system.settings_profile_elements WHERE profile_name IS NOT NULL ORDER BY profile_name, index FORMAT JSON"
+            .to_string(),
+    )
+    .await
+    .map_err(|err| err.access_denied_as("system.settings_profile_elements"))?;
+
+    let mut by_profile: BTreeMap<String, (Vec<String>, Vec<ProfileSetting>)> = BTreeMap::new();
+    for element in elements {
+        let (inherits, settings) = by_profile.entry(element.profile_name).or_default();
+        if let Some(parent) = element.inherit_profile {
+            inherits.push(parent);
+        }
+        if let Some(name) = element.setting_name {
+            settings.push(ProfileSetting {
+                name,
+                value: element.value,
+                min: element.min,
+                max: element.max,
+                writability: element
+                    .writability
+                    .filter(|writability| !writability.is_empty()),
+            });
+        }
+    }
+
+    Ok(profiles
+        .into_iter()
+        .map(|profile| {
+            let (inherits, settings) = by_profile.remove(&profile.name).unwrap_or_default();
+            SettingsProfile {
+                name: profile.name,
+                storage: profile.storage,
+                apply_to_all: profile.apply_to_all != 0,
+                apply_to_list: profile.apply_to_list,
+                apply_to_except: profile.apply_to_except,
+                inherits,
+                settings,
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,325 @@
+use crate::error::{HousecatError, UNKNOWN_TABLE};
+use crate::{clients, fetch_rows, quote_string_literal, require_value, ClickHouseConnectionInput};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How long gathered metadata is served before it is fetched again.
+const METADATA_TTL: Duration = Duration::from_secs(300);
+const MIN_REFRESH_INTERVAL_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ColumnCompletion {
+    name: String,
+    data_type: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FunctionCompletion {
+    name: String,
+    is_aggregate: bool,
+    case_insensitive: bool,
+    /// The function this name is an alias of, if it is one.
+    alias_to: Option<String>,
+}
+
+/// Everything the editor completes, for one connection.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AutocompleteMetadata {
+    /// Tables and their columns, in definition order, keyed by database
+    /// and table.
+    databases: BTreeMap<String, BTreeMap<String, Vec<ColumnCompletion>>>,
+    functions: Vec<FunctionCompletion>,
+    /// Empty on servers older than 24.11, which lack `system.keywords`.
+    keywords: Vec<String>,
+}
+
+struct CachedMetadata {
+    metadata: AutocompleteMetadata,
+    fetched_at: Instant,
+}
+
+/// Gathered metadata and background refresh tasks, both keyed by
+/// connection fingerprint.
+#[derive(Default)]
+pub(crate) struct AutocompleteCache {
+    entries: Mutex<HashMap<String, CachedMetadata>>,
+    refreshers: Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+impl AutocompleteCache {
+    fn fresh(&self, connection_id: &str) -> Option<AutocompleteMetadata> {
+        let entries = self.entries.lock().ok()?;
+        entries
+            .get(connection_id)
+            .filter(|entry| entry.fetched_at.elapsed() < METADATA_TTL)
+            .map(|entry| entry.metadata.clone())
+    }
+
+    fn store(&self, connection_id: String, metadata: AutocompleteMetadata) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(
+                connection_id,
+                CachedMetadata {
+                    metadata,
+                    fetched_at: Instant::now(),
+                },
+            );
+        }
+    }
+
+    /// Swaps in `tables` for one database of a cached entry, returning the
+    /// updated metadata, or `None` when nothing is cached to update.
+    fn replace_database(
+        &self,
+        connection_id: &str,
+        database: &str,
+        tables: BTreeMap<String, Vec<ColumnCompletion>>,
+    ) -> Option<AutocompleteMetadata> {
+        let mut entries = self.entries.lock().ok()?;
+        let entry = entries.get_mut(connection_id)?;
+        entry
+            .metadata
+            .databases
+            .insert(database.to_string(), tables);
+
+        Some(entry.metadata.clone())
+    }
+
+    fn stop_refresher(&self, connection_id: &str) -> bool {
+        let handle = self
+            .refreshers
+            .lock()
+            .ok()
+            .and_then(|mut refreshers| refreshers.remove(connection_id));
+
+        match handle {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseDatabaseRow {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseColumnRow {
+    database: String,
+    table: String,
+    name: String,
+    #[serde(rename = "type")]
+    data_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseFunctionRow {
+    name: String,
+    is_aggregate: u8,
+    case_insensitive: u8,
+    alias_to: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseKeywordRow {
+    keyword: String,
+}
+
+/// Tables and columns, of one database when `database` is given.
+async fn fetch_columns(
+    connection: &ClickHouseConnectionInput,
+    database: Option<&str>,
+) -> Result<BTreeMap<String, BTreeMap<String, Vec<ColumnCompletion>>>, HousecatError> {
+    let filter = database
+        .map(|database| format!(" WHERE database = {}", quote_string_literal(database)))
+        .unwrap_or_default();
+    let rows: Vec<ClickHouseColumnRow> = fetch_rows(
+        connection,
+        format!(
+            "SELECT database, table, name, type FROM system.columns{filter} ORDER BY database, table, position FORMAT JSON"
+        ),
+    )
+    .await?;
+
+    let mut databases: BTreeMap<String, BTreeMap<String, Vec<ColumnCompletion>>> = BTreeMap::new();
+    for row in rows {
+        databases
+            .entry(row.database)
+            .or_default()
+            .entry(row.table)
+            .or_default()
+            .push(ColumnCompletion {
+                name: row.name,
+                data_type: row.data_type,
+            });
+    }
+
+    Ok(databases)
+}
+
+async fn fetch_metadata(
+    connection: &ClickHouseConnectionInput,
+) -> Result<AutocompleteMetadata, HousecatError> {
+    let mut databases = fetch_columns(connection, None).await?;
+    // Databases without tables have no columns but should still complete.
+    let names: Vec<ClickHouseDatabaseRow> = fetch_rows(
+        connection,
+        "SELECT name FROM system.databases ORDER BY name FORMAT JSON".to_string(),
+    )
+    .await?;
+    for row in names {
+        databases.entry(row.name).or_default();
+    }
+
+    let functions: Vec<ClickHouseFunctionRow> = fetch_rows(
+        connection,
+        "SELECT name, is_aggregate, case_insensitive, alias_to FROM system.functions ORDER BY name FORMAT JSON"
+            .to_string(),
+    )
+    .await?;
+
+    let keywords = match fetch_rows::<ClickHouseKeywordRow>(
+        connection,
+        "SELECT keyword FROM system.keywords ORDER BY keyword FORMAT JSON".to_string(),
+    )
+    .await
+    {
+        Ok(rows) => rows.into_iter().map(|row| row.keyword).collect(),
+        Err(err) if err.clickhouse_code() == Some(UNKNOWN_TABLE) => Vec::new(),
+        Err(err) => return Err(err),
+    };
+
+    Ok(AutocompleteMetadata {
+        databases,
+        functions: functions
+            .into_iter()
+            .map(|row| FunctionCompletion {
+                name: row.name,
+                is_aggregate: row.is_aggregate != 0,
+                case_insensitive: row.case_insensitive != 0,
+                alias_to: (!row.alias_to.is_empty()).then_some(row.alias_to),
+            })
+            .collect(),
+        keywords,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AutocompleteInput {
+    connection: ClickHouseConnectionInput,
+    /// Re-fetches only this database's tables and columns, keeping the
+    /// rest of the cached metadata, such as after a `CREATE TABLE`.
+    database: Option<String>,
+    /// Fetches everything again even if the cached metadata is fresh.
+    refresh: Option<bool>,
+}
+
+/// Databases, tables, columns, functions and keywords for SQL completion.
+/// Served from a per-connection cache while it is younger than five
+/// minutes, so repeated calls from the editor stay cheap.
+#[tauri::command]
+pub(crate) async fn fetch_autocomplete_metadata(
+    input: AutocompleteInput,
+    cache: State<'_, AutocompleteCache>,
+) -> Result<AutocompleteMetadata, HousecatError> {
+    let connection_id = clients::fingerprint(&input.connection);
+
+    if let Some(database) = input.database.as_deref() {
+        let database = require_value(database, "Database")?;
+        let tables = fetch_columns(&input.connection, Some(database))
+            .await?
+            .remove(database)
+            .unwrap_or_default();
+        if let Some(metadata) = cache.replace_database(&connection_id, database, tables) {
+            return Ok(metadata);
+        }
+    } else if !input.refresh.unwrap_or(false) {
+        if let Some(metadata) = cache.fresh(&connection_id) {
+            return Ok(metadata);
+        }
+    }
+
+    let metadata = fetch_metadata(&input.connection).await?;
+    cache.store(connection_id, metadata.clone());
+    Ok(metadata)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AutocompleteRefreshInput {
+    connection: ClickHouseConnectionInput,
+    /// Seconds between refreshes; the cache lifetime when not given.
+    interval_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AutocompleteRefreshed {
+    connection_id: String,
+}
+
+/// Keeps a connection's metadata fresh in the background, emitting
+/// `autocomplete-refreshed` with the connection's fingerprint after each
+/// refresh. A failed refresh keeps the previous metadata and is retried on
+/// the next tick. Starting again replaces the running refresher.
+#[tauri::command]
+pub(crate) fn start_autocomplete_refresh(
+    app: AppHandle,
+    input: AutocompleteRefreshInput,
+    cache: State<'_, AutocompleteCache>,
+) -> String {
+    let connection_id = clients::fingerprint(&input.connection);
+    let interval = Duration::from_secs(
+        input
+            .interval_secs
+            .unwrap_or(METADATA_TTL.as_secs())
+            .max(MIN_REFRESH_INTERVAL_SECS),
+    );
+
+    let task_connection_id = connection_id.clone();
+    let connection = input.connection;
+    let handle = tauri::async_runtime::spawn(async move {
+        loop {
+            if let Ok(metadata) = fetch_metadata(&connection).await {
+                app.state::<AutocompleteCache>()
+                    .store(task_connection_id.clone(), metadata);
+                let _ = app.emit(
+                    "autocomplete-refreshed",
+                    AutocompleteRefreshed {
+                        connection_id: task_connection_id.clone(),
+                    },
+                );
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+
+    cache.stop_refresher(&connection_id);
+    if let Ok(mut refreshers) = cache.refreshers.lock() {
+        refreshers.insert(connection_id.clone(), handle);
+    }
+
+    connection_id
+}
+
+/// Stops the background refresher of the connection with this
+/// fingerprint. Returns whether one was running.
+#[tauri::command]
+pub(crate) fn stop_autocomplete_refresh(
+    connection_id: String,
+    cache: State<'_, AutocompleteCache>,
+) -> bool {
+    cache.stop_refresher(&connection_id)
+}
@@ -0,0 +1,245 @@
+use crate::error::HousecatError;
+use crate::settings::settings_params;
+use crate::statement::require_read_only;
+use crate::{
+    escape_identifier, fetch_rows, qualified_table, quote_string_literal, require_value,
+    run_clickhouse_query, run_clickhouse_query_with_params, ClickHouseConnectionInput,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QuerySummary {
+    read_rows: u64,
+    read_bytes: u64,
+    result_rows: u64,
+    pub(crate) written_rows: u64,
+    /// Server-side execution time; only reported by newer servers.
+    server_elapsed_ms: Option<f64>,
+}
+
+/// Reads the `X-ClickHouse-Summary` header. It is only complete when the
+/// query ran with `wait_end_of_query=1`, since headers are otherwise sent
+/// before execution finishes.
+pub(crate) fn parse_summary_header(response: &reqwest::Response) -> QuerySummary {
+    let summary: BTreeMap<String, String> = response
+        .headers()
+        .get("x-clickhouse-summary")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| serde_json::from_str(value).ok())
+        .unwrap_or_default();
+
+    let number = |key: &str| summary.get(key).and_then(|value| value.parse::<u64>().ok());
+
+    QuerySummary {
+        read_rows: number("read_rows").unwrap_or(0),
+        read_bytes: number("read_bytes").unwrap_or(0),
+        result_rows: number("result_rows").unwrap_or(0),
+        written_rows: number("written_rows").unwrap_or(0),
+        server_elapsed_ms: number("elapsed_ns").map(|ns| ns as f64 / 1_000_000.0),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CompareSettingsInput {
+    connection: ClickHouseConnectionInput,
+    query: String,
+    settings_a: BTreeMap<String, String>,
+    settings_b: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SettingsRun {
+    settings: BTreeMap<String, String>,
+    elapsed_ms: f64,
+    summary: QuerySummary,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SettingsComparison {
+    a: SettingsRun,
+    b: SettingsRun,
+}
+
+/// Executes `query` to completion without transferring its result, so the
+/// timing reflects server work rather than result size.
+async fn timed_run(
+    connection: &ClickHouseConnectionInput,
+    query: &str,
+    settings: BTreeMap<String, String>,
+) -> Result<SettingsRun, HousecatError> {
+    let mut params = settings_params(Some(&settings))?;
+    params.push(("wait_end_of_query", "1".to_string()));
+
+    let started = Instant::now();
+    let response =
+        run_clickhouse_query_with_params(connection, format!("{query} FORMAT Null"), &params)
+            .await?;
+    let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    Ok(SettingsRun {
+        summary: parse_summary_header(&response),
+        settings,
+        elapsed_ms,
+    })
+}
+
+/// Runs the same read-only query under two settings maps, one after the
+/// other, so their timings and rows read can be compared.
+#[tauri::command]
+pub(crate) async fn compare_with_settings(
+    input: CompareSettingsInput,
+) -> Result<SettingsComparison, HousecatError> {
+    let query = require_read_only(&input.query)?;
+
+    let a = timed_run(&input.connection, query, input.settings_a).await?;
+    let b = timed_run(&input.connection, query, input.settings_b).await?;
+
+    Ok(SettingsComparison { a, b })
+}
+
+/// Upper bound for `benchmark_throughput`; at roughly 20 bytes per row this
+/// is about a gigabyte of transfer.
+const MAX_THROUGHPUT_ROWS: u64 = 50_000_000;
+const DEFAULT_THROUGHPUT_ROWS: u64 = 1_000_000;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ThroughputInput {
+    connection: ClickHouseConnectionInput,
+    row_count: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ThroughputResult {
+    rows: u64,
+    bytes: u64,
+    elapsed_ms: f64,
+    rows_per_sec: f64,
+    mb_per_sec: f64,
+}
+
+/// Measures end-to-end HTTP throughput by streaming `numbers(row_count)` as
+/// `JSONEachRow` and discarding it chunk by chunk, so nothing is buffered.
+#[tauri::command]
+pub(crate) async fn benchmark_throughput(
+    input: ThroughputInput,
+) -> Result<ThroughputResult, HousecatError> {
+    let row_count = input
+        .row_count
+        .unwrap_or(DEFAULT_THROUGHPUT_ROWS)
+        .clamp(1, MAX_THROUGHPUT_ROWS);
+
+    let started = Instant::now();
+    let mut response = run_clickhouse_query(
+        &input.connection,
+        format!("SELECT number FROM numbers({row_count}) FORMAT JSONEachRow"),
+    )
+    .await?;
+
+    let mut rows = 0;
+    let mut bytes = 0;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|err| HousecatError::Response(err.to_string()))?
+    {
+        bytes += chunk.len() as u64;
+        rows += chunk.iter().filter(|byte| **byte == b'\n').count() as u64;
+    }
+
+    let elapsed = started.elapsed().as_secs_f64().max(f64::EPSILON);
+
+    Ok(ThroughputResult {
+        rows,
+        bytes,
+        elapsed_ms: elapsed * 1000.0,
+        rows_per_sec: rows as f64 / elapsed,
+        mb_per_sec: bytes as f64 / 1_000_000.0 / elapsed,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WarmCacheInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseKeyColumnRow {
+    name: String,
+    is_in_sorting_key: u8,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WarmCacheResult {
+    columns: Vec<String>,
+    elapsed_ms: f64,
+    summary: QuerySummary,
+}
+
+/// Reads the sorting-key columns of every part, or the first column when
+/// the table has no sorting key, so their marks and blocks are cached
+/// before a benchmark and the first timed run measures the query rather
+/// than cold disk reads. This is a benchmarking aid only: results never
+/// depend on it, and the caches may evict the data again at any time.
+#[tauri::command]
+pub(crate) async fn warm_cache(input: WarmCacheInput) -> Result<WarmCacheResult, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+
+    let query = format!(
+        "SELECT name, is_in_sorting_key FROM system.columns WHERE database = {} AND table = {} ORDER BY position FORMAT JSON",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+    let rows: Vec<ClickHouseKeyColumnRow> = fetch_rows(&input.connection, query).await?;
+    let Some(first) = rows.first() else {
+        return Err(HousecatError::invalid_input(format!(
+            "Table {schema}.{table} does not exist"
+        )));
+    };
+
+    let mut columns: Vec<String> = rows
+        .iter()
+        .filter(|row| row.is_in_sorting_key != 0)
+        .map(|row| row.name.clone())
+        .collect();
+    if columns.is_empty() {
+        columns.push(first.name.clone());
+    }
+
+    let select_list: Vec<String> = columns
+        .iter()
+        .map(|column| format!("`{}`", escape_identifier(column)))
+        .collect();
+    let query = format!(
+        "SELECT {} FROM {} FORMAT Null",
+        select_list.join(", "),
+        qualified_table(schema, table)
+    );
+    // The query cache would answer a repeat without reading anything.
+    let params = [
+        ("wait_end_of_query", "1".to_string()),
+        ("use_query_cache", "0".to_string()),
+    ];
+
+    let started = Instant::now();
+    let response = run_clickhouse_query_with_params(&input.connection, query, &params).await?;
+    let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    Ok(WarmCacheResult {
+        columns,
+        elapsed_ms,
+        summary: parse_summary_header(&response),
+    })
+}
@@ -0,0 +1,128 @@
+use crate::error::HousecatError;
+use crate::{build_client, tunnels, ClickHouseConnectionInput};
+use reqwest::Client;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+
+/// Identifies which pooled client a connection may share. Credentials are
+/// sent per request and deliberately left out, so they never sit in the
+/// map; the timeouts and TLS options are part of the client's
+/// configuration and so part of the key.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct ClientKey {
+    host: String,
+    port: u16,
+    secure: bool,
+    connect_timeout_secs: u64,
+    read_timeout_secs: u64,
+    accept_invalid_certs: bool,
+    ca_cert_pem: Option<String>,
+    ca_cert_path: Option<String>,
+    client_cert_path: Option<String>,
+    client_key_path: Option<String>,
+    /// The local end of the connection's SSH tunnel, so a reopened tunnel
+    /// gets a fresh client.
+    tunnel: Option<SocketAddr>,
+}
+
+impl ClientKey {
+    fn for_connection(input: &ClickHouseConnectionInput) -> Self {
+        Self {
+            host: input.host.trim().to_ascii_lowercase(),
+            port: input.port,
+            secure: input.secure,
+            connect_timeout_secs: input.connect_timeout(),
+            read_timeout_secs: input.read_timeout(),
+            accept_invalid_certs: input.accepts_invalid_certs(),
+            ca_cert_pem: input.ca_cert_pem().map(str::to_string),
+            ca_cert_path: input.ca_cert_path().map(str::to_string),
+            client_cert_path: input.client_cert_path().map(str::to_string),
+            client_key_path: input.client_key_path().map(str::to_string),
+            tunnel: tunnels::local_address(input),
+        }
+    }
+}
+
+/// Clients are shared process-wide rather than held in managed state:
+/// every query helper needs one, including those running in background
+/// tasks such as the progress poller, which have no `State` to draw from.
+/// A `Client` is a handle to a connection pool, so clones are cheap.
+static CLIENTS: Mutex<BTreeMap<ClientKey, Client>> = Mutex::new(BTreeMap::new());
+
+/// Returns the pooled client for `input`'s server, creating it on first use
+/// so later queries reuse its open connections and TLS sessions.
+pub(crate) fn client_for(input: &ClickHouseConnectionInput) -> Result<Client, HousecatError> {
+    let key = ClientKey::for_connection(input);
+    let mut clients = CLIENTS
+        .lock()
+        .map_err(|_| HousecatError::Client("client cache is poisoned".to_string()))?;
+
+    if let Some(client) = clients.get(&key) {
+        return Ok(client.clone());
+    }
+
+    let client = build_client(input)?;
+    clients.insert(key, client.clone());
+    Ok(client)
+}
+
+/// Drops the pooled client for `input`'s server, so the next query opens
+/// fresh connections, for instance after the network changed.
+pub(crate) fn evict(input: &ClickHouseConnectionInput) {
+    if let Ok(mut clients) = CLIENTS.lock() {
+        clients.remove(&ClientKey::for_connection(input));
+    }
+}
+
+/// Closes the pooled connections to `input`'s server, leaving other
+/// servers' alone. Useful when one server's kept-alive sockets went stale,
+/// such as after it restarted.
+#[tauri::command]
+pub(crate) fn reset_connection_pool(input: ClickHouseConnectionInput) {
+    evict(&input);
+}
+
+/// Closes every pooled connection. Useful after switching networks or VPNs,
+/// when kept-alive sockets may silently point nowhere.
+#[tauri::command]
+pub(crate) fn reset_all_connection_pools() -> Result<(), HousecatError> {
+    CLIENTS
+        .lock()
+        .map_err(|_| HousecatError::Client("client cache is poisoned".to_string()))?
+        .clear();
+    Ok(())
+}
+
+/// FNV-1a, chosen over `DefaultHasher` because its output is fixed by
+/// definition, so fingerprints stay valid across Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// A stable identifier for who is connecting where: host, port, TLS, user
+/// and default database. The password is left out on purpose, so changing
+/// it keeps a connection's cached state, and the value can be stored
+/// without exposing it. Unlike the pool's `ClientKey`, the user is part of
+/// the identity, since what a cache holds may depend on their grants.
+pub(crate) fn fingerprint(input: &ClickHouseConnectionInput) -> String {
+    let database = input.database.as_deref().map(str::trim).unwrap_or("");
+    // JSON-encoded so no choice of field values can make two identities
+    // collide before hashing.
+    let identity = serde_json::json!([
+        input.host.trim().to_ascii_lowercase(),
+        input.port,
+        input.secure,
+        input.username.trim(),
+        database,
+    ]);
+
+    format!("{:016x}", fnv1a(identity.to_string().as_bytes()))
+}
+
+#[tauri::command]
+pub(crate) fn connection_fingerprint(input: ClickHouseConnectionInput) -> String {
+    fingerprint(&input)
+}
@@ -0,0 +1,398 @@
+use crate::error::HousecatError;
+use crate::mutations::HealthLevel;
+use crate::{fetch_rows, quote_string_literal, ClickHouseConnectionInput};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Replicas this many seconds behind are a warning, or critical beyond
+/// the second threshold.
+const REPLICA_DELAY_WARNING_SECS: u64 = 30;
+const REPLICA_DELAY_CRITICAL_SECS: u64 = 300;
+const REPLICA_QUEUE_WARNING: u64 = 20;
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseClusterRow {
+    cluster: String,
+    shard_num: u32,
+    shard_weight: u32,
+    replica_num: u32,
+    host_name: String,
+    host_address: String,
+    port: u16,
+    is_local: u8,
+    errors_count: u64,
+    slowdown_count: u64,
+    estimated_recovery_time: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ClusterReplica {
+    replica_num: u32,
+    host_name: String,
+    host_address: String,
+    /// The native protocol port, as `preview_remote` takes it in
+    /// `host:port` to read from this replica specifically.
+    port: u16,
+    /// Whether this is the server the connection talks to.
+    is_local: bool,
+    errors_count: u64,
+    slowdown_count: u64,
+    /// Seconds until the replica's error count is forgiven.
+    estimated_recovery_time: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ClusterShard {
+    shard_num: u32,
+    shard_weight: u32,
+    replicas: Vec<ClusterReplica>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ClusterTopology {
+    name: String,
+    shards: Vec<ClusterShard>,
+}
+
+/// Every cluster in the server's configuration, with its shards and their
+/// replicas in order.
+#[tauri::command]
+pub(crate) async fn fetch_cluster_topology(
+    input: ClickHouseConnectionInput,
+) -> Result<Vec<ClusterTopology>, HousecatError> {
+    let rows: Vec<ClickHouseClusterRow> = fetch_rows(
+        &input,
+        "SELECT cluster, shard_num, shard_weight, replica_num, host_name, host_address, port, \
+        toUInt8(is_local) AS is_local, errors_count, slowdown_count, estimated_recovery_time \
+        FROM system.clusters ORDER BY cluster, shard_num, replica_num FORMAT JSON"
+            .to_string(),
+    )
+    .await?;
+
+    let mut clusters: Vec<ClusterTopology> = Vec::new();
+    for row in rows {
+        if clusters
+            .last()
+            .is_none_or(|cluster| cluster.name != row.cluster)
+        {
+            clusters.push(ClusterTopology {
+                name: row.cluster.clone(),
+                shards: Vec::new(),
+            });
+        }
+        let shards = &mut clusters.last_mut().expect("cluster was just pushed").shards;
+        if shards
+            .last()
+            .is_none_or(|shard| shard.shard_num != row.shard_num)
+        {
+            shards.push(ClusterShard {
+                shard_num: row.shard_num,
+                shard_weight: row.shard_weight,
+                replicas: Vec::new(),
+            });
+        }
+
+        shards
+            .last_mut()
+            .expect("shard was just pushed")
+            .replicas
+            .push(ClusterReplica {
+                replica_num: row.replica_num,
+                host_name: row.host_name,
+                host_address: row.host_address,
+                port: row.port,
+                is_local: row.is_local != 0,
+                errors_count: row.errors_count,
+                slowdown_count: row.slowdown_count,
+                estimated_recovery_time: row.estimated_recovery_time,
+            });
+    }
+
+    Ok(clusters)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReplicaStatusInput {
+    connection: ClickHouseConnectionInput,
+    schema: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseReplicaRow {
+    database: String,
+    table: String,
+    replica_name: String,
+    is_leader: u8,
+    is_readonly: u8,
+    is_session_expired: u8,
+    absolute_delay: u64,
+    queue_size: u64,
+    inserts_in_queue: u64,
+    merges_in_queue: u64,
+    log_max_index: u64,
+    log_pointer: u64,
+    total_replicas: u64,
+    active_replicas: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReplicaStatus {
+    database: String,
+    table: String,
+    replica_name: String,
+    health: HealthLevel,
+    is_leader: bool,
+    is_readonly: bool,
+    is_session_expired: bool,
+    /// Seconds this replica is behind the freshest one.
+    absolute_delay: u64,
+    queue_size: u64,
+    inserts_in_queue: u64,
+    merges_in_queue: u64,
+    /// Replication log entries not yet fetched into the queue.
+    log_entries_behind: u64,
+    total_replicas: u64,
+    active_replicas: u64,
+}
+
+/// A read-only replica, one cut off from Keeper or one minutes behind is
+/// critical: it cannot take inserts or serves stale data. Lag, a long
+/// queue or missing peers are a warning.
+fn replica_health_level(row: &ClickHouseReplicaRow) -> HealthLevel {
+    if row.is_readonly != 0
+        || row.is_session_expired != 0
+        || row.absolute_delay > REPLICA_DELAY_CRITICAL_SECS
+    {
+        HealthLevel::Critical
+    } else if row.absolute_delay > REPLICA_DELAY_WARNING_SECS
+        || row.queue_size > REPLICA_QUEUE_WARNING
+        || row.active_replicas < row.total_replicas
+    {
+        HealthLevel::Warning
+    } else {
+        HealthLevel::Healthy
+    }
+}
+
+/// Replication state of every replicated table on this server, optionally
+/// only in `schema`.
+#[tauri::command]
+pub(crate) async fn fetch_replica_status(
+    input: ReplicaStatusInput,
+) -> Result<Vec<ReplicaStatus>, HousecatError> {
+    let filter = match input.schema.as_deref().map(str::trim) {
+        Some(schema) if !schema.is_empty() => {
+            format!("WHERE database = {}", quote_string_literal(schema))
+        }
+        _ => String::new(),
+    };
+    let query = format!(
+        "SELECT database, table, replica_name, toUInt8(is_leader) AS is_leader, \
+        toUInt8(is_readonly) AS is_readonly, toUInt8(is_session_expired) AS is_session_expired, \
+        absolute_delay, queue_size, inserts_in_queue, merges_in_queue, log_max_index, log_pointer, \
+        total_replicas, active_replicas \
+        FROM system.replicas {filter} ORDER BY database, table FORMAT JSON"
+    );
+
+    let rows: Vec<ClickHouseReplicaRow> = fetch_rows(&input.connection, query).await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ReplicaStatus {
+            health: replica_health_level(&row),
+            log_entries_behind: row.log_max_index.saturating_sub(row.log_pointer),
+            is_leader: row.is_leader != 0,
+            is_readonly: row.is_readonly != 0,
+            is_session_expired: row.is_session_expired != 0,
+            database: row.database,
+            table: row.table,
+            replica_name: row.replica_name,
+            absolute_delay: row.absolute_delay,
+            queue_size: row.queue_size,
+            inserts_in_queue: row.inserts_in_queue,
+            merges_in_queue: row.merges_in_queue,
+            total_replicas: row.total_replicas,
+            active_replicas: row.active_replicas,
+        })
+        .collect())
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseEngineRow {
+    database: String,
+    name: String,
+    engine_full: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseLocalTableRow {
+    database: String,
+    name: String,
+    engine: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DistributedTable {
+    database: String,
+    table: String,
+    cluster: String,
+    local_database: String,
+    local_table: String,
+    /// The underlying table's engine on this server; `None` when this
+    /// server holds no such table.
+    local_engine: Option<String>,
+    sharding_key: Option<String>,
+}
+
+/// The top-level arguments of `Engine(a, b, ...)`, split at commas outside
+/// quotes and nested parentheses. Anything after the closing parenthesis,
+/// such as `SETTINGS`, is ignored.
+fn engine_arguments(engine_full: &str) -> Vec<String> {
+    let Some(start) = engine_full.find('(') else {
+        return Vec::new();
+    };
+
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut depth = 0usize;
+    let mut chars = engine_full[start + 1..].chars();
+
+    while let Some(c) = chars.next() {
+        if let Some(open) = quote {
+            current.push(c);
+            if c == '\\' {
+                current.extend(chars.next());
+            } else if c == open {
+                quote = None;
+            }
+            continue;
+        }
+
+        match c {
+            '\'' | '`' | '"' => {
+                quote = Some(c);
+                current.push(c);
+            }
+            '(' => {
+                depth += 1;
+                current.push(c);
+            }
+            ')' if depth == 0 => break,
+            ')' => {
+                depth -= 1;
+                current.push(c);
+            }
+            ',' if depth == 0 => arguments.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    arguments.push(current);
+
+    arguments
+        .into_iter()
+        .map(|argument| argument.trim().to_string())
+        .filter(|argument| !argument.is_empty())
+        .collect()
+}
+
+/// A quoted string or identifier without its quotes; anything else as is.
+fn unquote(argument: &str) -> String {
+    for quote in ['\'', '`', '"'] {
+        if let Some(inner) = argument
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            let mut value = String::new();
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                if c == '\\' {
+                    value.extend(chars.next());
+                } else {
+                    value.push(c);
+                }
+            }
+            return value;
+        }
+    }
+
+    argument.to_string()
+}
+
+/// Maps each `Distributed` table to the cluster and local table it routes
+/// to, as declared by `Distributed(cluster, database, table[, sharding_key])`,
+/// and whether this server holds that local table.
+#[tauri::command]
+pub(crate) async fn fetch_distributed_tables(
+    input: ClickHouseConnectionInput,
+) -> Result<Vec<DistributedTable>, HousecatError> {
+    let rows: Vec<ClickHouseEngineRow> = fetch_rows(
+        &input,
+        "SELECT database, name, engine_full FROM system.tables WHERE engine = 'Distributed' ORDER BY database, name FORMAT JSON"
+            .to_string(),
+    )
+    .await?;
+
+    let mut tables: Vec<DistributedTable> = rows
+        .into_iter()
+        .map(|row| {
+            let arguments = engine_arguments(&row.engine_full);
+            let argument = |index: usize| arguments.get(index).map(|value| unquote(value));
+            // An empty or computed database, such as currentDatabase(),
+            // means the Distributed table's own.
+            let local_database = argument(1)
+                .filter(|database| !database.is_empty() && !database.ends_with(')'))
+                .unwrap_or_else(|| row.database.clone());
+
+            DistributedTable {
+                cluster: argument(0).unwrap_or_default(),
+                local_database,
+                local_table: argument(2).unwrap_or_default(),
+                local_engine: None,
+                sharding_key: arguments.get(3).cloned(),
+                database: row.database,
+                table: row.name,
+            }
+        })
+        .collect();
+
+    if tables.is_empty() {
+        return Ok(tables);
+    }
+
+    let local_filter = tables
+        .iter()
+        .map(|table| {
+            format!(
+                "({}, {})",
+                quote_string_literal(&table.local_database),
+                quote_string_literal(&table.local_table)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let engines: BTreeMap<(String, String), String> = fetch_rows::<ClickHouseLocalTableRow>(
+        &input,
+        format!(
+            "SELECT database, name, engine FROM system.tables WHERE (database, name) IN ({local_filter}) FORMAT JSON"
+        ),
+    )
+    .await?
+    .into_iter()
+    .map(|row| ((row.database, row.name), row.engine))
+    .collect();
+
+    for table in &mut tables {
+        table.local_engine = engines
+            .get(&(table.local_database.clone(), table.local_table.clone()))
+            .cloned();
+    }
+
+    Ok(tables)
+}
@@ -0,0 +1,265 @@
+use crate::error::HousecatError;
+use crate::tunnels::SshTunnelConfig;
+use crate::{fetch_connection_status, require_value, ClickHouseConnectionInput, ConnectionStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const CONNECTIONS_FILE: &str = "connections.json";
+
+/// Service name passwords are filed under in the OS keychain, with the
+/// profile id as the account.
+const KEYCHAIN_SERVICE: &str = "housecat";
+
+/// Serializes read-modify-write cycles on the connections file.
+static CONNECTIONS_LOCK: Mutex<()> = Mutex::new(());
+
+/// A saved connection as stored on disk. The password is never part of it;
+/// it lives in the OS keychain.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredConnection {
+    name: String,
+    host: String,
+    port: u16,
+    username: String,
+    database: Option<String>,
+    secure: bool,
+    #[serde(default)]
+    read_only: bool,
+    connect_timeout_secs: Option<u64>,
+    read_timeout_secs: Option<u64>,
+    #[serde(default)]
+    accept_invalid_certs: bool,
+    ca_cert_pem: Option<String>,
+    ca_cert_path: Option<String>,
+    client_cert_path: Option<String>,
+    client_key_path: Option<String>,
+    ssh_tunnel: Option<SshTunnelConfig>,
+}
+
+/// A saved connection as shown to the frontend: the stored fields plus
+/// whether a password is in the keychain, never the password itself.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ConnectionProfile {
+    id: String,
+    #[serde(flatten)]
+    connection: StoredConnection,
+    has_password: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SaveConnectionInput {
+    /// The profile to update; a new one is created when absent.
+    id: Option<String>,
+    #[serde(flatten)]
+    connection: StoredConnection,
+    /// Stored in the keychain when given. Leaving it out keeps the saved
+    /// password; an empty string removes it.
+    password: Option<String>,
+}
+
+fn keychain_entry(id: &str) -> Result<keyring::Entry, HousecatError> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, id)
+        .map_err(|err| HousecatError::Keychain(err.to_string()))
+}
+
+fn load_password(id: &str) -> Result<Option<String>, HousecatError> {
+    match keychain_entry(id)?.get_password() {
+        Ok(password) => Ok(Some(password)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(HousecatError::Keychain(err.to_string())),
+    }
+}
+
+fn delete_password(id: &str) -> Result<(), HousecatError> {
+    match keychain_entry(id)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(err) => Err(HousecatError::Keychain(err.to_string())),
+    }
+}
+
+fn connections_path(app: &AppHandle) -> Result<PathBuf, HousecatError> {
+    let directory = app
+        .path()
+        .app_data_dir()
+        .map_err(|err| HousecatError::Io(format!("Could not locate app data: {err}")))?;
+
+    Ok(directory.join(CONNECTIONS_FILE))
+}
+
+fn read_all(path: &Path) -> Result<BTreeMap<String, StoredConnection>, HousecatError> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(err) => {
+            return Err(HousecatError::Io(format!(
+                "Could not read {}: {err}",
+                path.display()
+            )))
+        }
+    };
+
+    serde_json::from_str(&contents)
+        .map_err(|err| HousecatError::Io(format!("Could not parse {}: {err}", path.display())))
+}
+
+/// Writes to a temporary file first, so a crash mid-write leaves the saved
+/// connections intact.
+fn write_all(
+    path: &Path,
+    connections: &BTreeMap<String, StoredConnection>,
+) -> Result<(), HousecatError> {
+    let write_error = |err: std::io::Error| {
+        HousecatError::Io(format!("Could not write {}: {err}", path.display()))
+    };
+
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory).map_err(write_error)?;
+    }
+    let contents = serde_json::to_string_pretty(connections)
+        .map_err(|err| HousecatError::Io(format!("Could not serialize connections: {err}")))?;
+    let temporary = path.with_extension("json.tmp");
+    std::fs::write(&temporary, contents).map_err(write_error)?;
+    std::fs::rename(&temporary, path).map_err(write_error)
+}
+
+fn profile(id: String, connection: StoredConnection) -> Result<ConnectionProfile, HousecatError> {
+    let has_password = load_password(&id)?.is_some();
+
+    Ok(ConnectionProfile {
+        id,
+        connection,
+        has_password,
+    })
+}
+
+/// Saved connections, sorted by name, without their passwords.
+#[tauri::command]
+pub(crate) fn list_connections(app: AppHandle) -> Result<Vec<ConnectionProfile>, HousecatError> {
+    let path = connections_path(&app)?;
+
+    let _guard = CONNECTIONS_LOCK
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    let mut profiles = read_all(&path)?
+        .into_iter()
+        .map(|(id, connection)| profile(id, connection))
+        .collect::<Result<Vec<_>, _>>()?;
+    profiles.sort_by(|a, b| a.connection.name.cmp(&b.connection.name));
+
+    Ok(profiles)
+}
+
+/// Creates or updates a saved connection. Everything but the password goes
+/// to a file in the app's data directory; the password goes to the OS
+/// keychain.
+#[tauri::command]
+pub(crate) fn save_connection(
+    app: AppHandle,
+    input: SaveConnectionInput,
+) -> Result<ConnectionProfile, HousecatError> {
+    require_value(&input.connection.name, "Connection name")?;
+    require_value(&input.connection.host, "Host")?;
+    require_value(&input.connection.username, "Username")?;
+    let path = connections_path(&app)?;
+
+    let _guard = CONNECTIONS_LOCK
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    let mut connections = read_all(&path)?;
+    let id = match input.id.as_deref().map(str::trim) {
+        Some(id) if !id.is_empty() => {
+            if !connections.contains_key(id) {
+                return Err(HousecatError::ConnectionNotFound(id.to_string()));
+            }
+            id.to_string()
+        }
+        _ => uuid::Uuid::new_v4().to_string(),
+    };
+
+    match input.password.as_deref() {
+        Some("") => delete_password(&id)?,
+        Some(password) => keychain_entry(&id)?
+            .set_password(password)
+            .map_err(|err| HousecatError::Keychain(err.to_string()))?,
+        None => {}
+    }
+
+    connections.insert(id.clone(), input.connection.clone());
+    write_all(&path, &connections)?;
+
+    profile(id, input.connection)
+}
+
+/// Removes a saved connection and its keychain entry. Unknown ids are
+/// ignored, so deleting twice is harmless.
+#[tauri::command]
+pub(crate) fn delete_connection(app: AppHandle, id: String) -> Result<(), HousecatError> {
+    let id = require_value(&id, "Connection id")?;
+    let path = connections_path(&app)?;
+
+    let _guard = CONNECTIONS_LOCK
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    let mut connections = read_all(&path)?;
+    if connections.remove(id).is_some() {
+        write_all(&path, &connections)?;
+    }
+
+    delete_password(id)
+}
+
+/// The full connection input for a saved profile, password included, for
+/// running commands against it.
+fn saved_connection_input(
+    app: &AppHandle,
+    id: &str,
+) -> Result<ClickHouseConnectionInput, HousecatError> {
+    let path = connections_path(app)?;
+    let stored = {
+        let _guard = CONNECTIONS_LOCK
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        read_all(&path)?
+            .remove(id)
+            .ok_or_else(|| HousecatError::ConnectionNotFound(id.to_string()))?
+    };
+
+    Ok(ClickHouseConnectionInput {
+        host: stored.host,
+        port: stored.port,
+        username: stored.username,
+        password: load_password(id)?.unwrap_or_default(),
+        database: stored.database,
+        secure: stored.secure,
+        connect_timeout_secs: stored.connect_timeout_secs,
+        read_timeout_secs: stored.read_timeout_secs,
+        session_id: None,
+        read_only: Some(stored.read_only),
+        timeout_secs: None,
+        accept_invalid_certs: Some(stored.accept_invalid_certs),
+        ca_cert_pem: stored.ca_cert_pem,
+        ca_cert_path: stored.ca_cert_path,
+        client_cert_path: stored.client_cert_path,
+        client_key_path: stored.client_key_path,
+        ssh_tunnel: stored.ssh_tunnel,
+    })
+}
+
+/// Connects with a saved profile and its keychain password and reports the
+/// server's version and latency, like `fetch_connection_status`.
+#[tauri::command]
+pub(crate) async fn test_connection(
+    app: AppHandle,
+    id: String,
+) -> Result<ConnectionStatus, HousecatError> {
+    let id = require_value(&id, "Connection id")?;
+    let input = saved_connection_input(&app, id)?;
+
+    fetch_connection_status(input).await
+}
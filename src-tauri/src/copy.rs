@@ -0,0 +1,159 @@
+use crate::error::HousecatError;
+use crate::schema::table_exists_in;
+use crate::statement::require_read_only;
+use crate::{
+    build_client, qualified_table, require_confirmation, require_value, run_clickhouse_query,
+    run_clickhouse_query_on, tunnels, ClickHouseConnectionInput,
+};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+/// Rows are inserted in batches of at most this many rows or bytes, so
+/// neither side has to hold the whole copy in memory.
+const COPY_BATCH_ROWS: u64 = 10_000;
+const COPY_BATCH_BYTES: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CopyTableDataInput {
+    src_connection: ClickHouseConnectionInput,
+    src_query: String,
+    dst_connection: ClickHouseConnectionInput,
+    dst_schema: String,
+    dst_table: String,
+    confirmation: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CopyResult {
+    rows_copied: u64,
+    batches: u64,
+}
+
+struct BatchWriter<'a> {
+    client: Client,
+    connection: &'a ClickHouseConnectionInput,
+    insert: String,
+    batch: Vec<u8>,
+    batch_rows: u64,
+    rows_copied: u64,
+    batches: u64,
+}
+
+impl BatchWriter<'_> {
+    async fn push_line(&mut self, line: &[u8]) -> Result<(), HousecatError> {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return Ok(());
+        }
+
+        self.batch.extend_from_slice(line);
+        self.batch.push(b'\n');
+        self.batch_rows += 1;
+
+        if self.batch_rows >= COPY_BATCH_ROWS || self.batch.len() >= COPY_BATCH_BYTES {
+            self.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), HousecatError> {
+        if self.batch_rows == 0 {
+            return Ok(());
+        }
+
+        let mut body = Vec::with_capacity(self.insert.len() + self.batch.len());
+        body.extend_from_slice(self.insert.as_bytes());
+        body.append(&mut self.batch);
+
+        run_clickhouse_query_on(&self.client, self.connection, body, &[])
+            .await
+            .map_err(|err| self.interrupted(err.to_string()))?;
+
+        self.rows_copied += self.batch_rows;
+        self.batch_rows = 0;
+        self.batches += 1;
+        Ok(())
+    }
+
+    fn interrupted(&self, reason: String) -> HousecatError {
+        HousecatError::CopyInterrupted {
+            rows_copied: self.rows_copied,
+            reason,
+        }
+    }
+}
+
+/// Streams the rows of `src_query` from one server into a table on another
+/// as `JSONEachRow`, in bounded batches. Batches already inserted stay in
+/// place when a later one fails; the error reports how many rows made it.
+#[tauri::command]
+pub(crate) async fn copy_table_data(
+    input: CopyTableDataInput,
+) -> Result<CopyResult, HousecatError> {
+    let src_query = require_read_only(&input.src_query)?;
+    let dst_schema = require_value(&input.dst_schema, "Destination schema")?;
+    let dst_table = require_value(&input.dst_table, "Destination table")?;
+    require_confirmation(&input.confirmation, dst_table)?;
+
+    if src_query.to_uppercase().contains("FORMAT ") {
+        return Err(HousecatError::invalid_input(
+            "The source query must not set its own FORMAT",
+        ));
+    }
+    if !table_exists_in(&input.dst_connection, dst_schema, dst_table).await? {
+        return Err(HousecatError::invalid_input(format!(
+            "Table {dst_schema}.{dst_table} does not exist on the destination"
+        )));
+    }
+
+    tunnels::ensure(&input.dst_connection).await?;
+    let mut writer = BatchWriter {
+        client: build_client(&input.dst_connection)?,
+        connection: &input.dst_connection,
+        insert: format!(
+            "INSERT INTO {} FORMAT JSONEachRow\n",
+            qualified_table(dst_schema, dst_table)
+        ),
+        batch: Vec::new(),
+        batch_rows: 0,
+        rows_copied: 0,
+        batches: 0,
+    };
+
+    let mut response = run_clickhouse_query(
+        &input.src_connection,
+        format!("{src_query} FORMAT JSONEachRow"),
+    )
+    .await?;
+
+    let mut pending = Vec::new();
+    loop {
+        let chunk = response
+            .chunk()
+            .await
+            .map_err(|err| writer.interrupted(err.to_string()))?;
+        let Some(chunk) = chunk else {
+            break;
+        };
+
+        pending.extend_from_slice(&chunk);
+        let Some(last_newline) = pending.iter().rposition(|byte| *byte == b'\n') else {
+            continue;
+        };
+
+        let complete: Vec<u8> = pending.drain(..=last_newline).collect();
+        for line in complete.split(|byte| *byte == b'\n') {
+            writer.push_line(line).await?;
+        }
+    }
+
+    writer.push_line(&pending).await?;
+    writer.flush().await?;
+
+    Ok(CopyResult {
+        rows_copied: writer.rows_copied,
+        batches: writer.batches,
+    })
+}
@@ -0,0 +1,373 @@
+use crate::error::HousecatError;
+use crate::{base_type, quote_string_literal};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+/// The column types a filter value can be normalized for.
+#[derive(Debug, Clone, Copy)]
+enum DateTarget {
+    Date,
+    Date32,
+    DateTime,
+    DateTime64(usize),
+}
+
+impl DateTarget {
+    fn parse(data_type: &str) -> Option<Self> {
+        let base = base_type(data_type);
+        let (name, args) = match base.split_once('(') {
+            Some((name, rest)) => (name.trim(), Some(rest.strip_suffix(')')?)),
+            None => (base, None),
+        };
+
+        match (name, args) {
+            ("Date", None) => Some(Self::Date),
+            ("Date32", None) => Some(Self::Date32),
+            // The optional argument is a timezone, which only decides how
+            // the server reads wall-clock values.
+            ("DateTime", _) => Some(Self::DateTime),
+            ("DateTime64", Some(args)) => {
+                let precision = args.split(',').next()?.trim().parse().ok()?;
+                (precision <= 9).then_some(Self::DateTime64(precision))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether a day and second of day fit the type. Wall-clock values are
+    /// checked as if they were UTC, which is off by at most the column's
+    /// timezone offset at the very ends of the range.
+    fn contains(self, days: i64, seconds: i64) -> bool {
+        match self {
+            Self::Date => (0..=65_535).contains(&days),
+            // 1900-01-01 to 2299-12-31.
+            Self::Date32 | Self::DateTime64(_) => (-25_567..=120_529).contains(&days),
+            Self::DateTime => {
+                (0..=i64::from(u32::MAX)).contains(&(days * SECONDS_PER_DAY + seconds))
+            }
+        }
+    }
+}
+
+/// A parsed filter value: the calendar day and time as written, plus the
+/// UTC offset when the value names an absolute instant.
+#[derive(Debug)]
+struct ParsedDateTime {
+    days: i64,
+    seconds: i64,
+    fraction: String,
+    offset: Option<i64>,
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn is_digits(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Parses a fixed-width run of digits such as the `MM` in `YYYY-MM-DD`.
+fn parse_field(text: &str, width: usize, name: &str) -> Result<i64, String> {
+    if text.len() != width || !is_digits(text) {
+        return Err(format!("the {name} must be {width} digits"));
+    }
+
+    text.parse()
+        .map_err(|_| format!("the {name} is not a number"))
+}
+
+/// Unix timestamps, with their unit told apart by magnitude: anything below
+/// 10^11 is seconds (up to the year 5138), then milliseconds, microseconds
+/// and nanoseconds.
+fn parse_epoch(value: &str) -> Result<ParsedDateTime, String> {
+    let (negative, unsigned) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value),
+    };
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+
+    let integer: i128 = integer
+        .parse()
+        .map_err(|_| "the timestamp is too large".to_string())?;
+    let nanos_per_unit = match integer {
+        0..=99_999_999_999 => NANOS_PER_SECOND,
+        100_000_000_000..=99_999_999_999_999 => 1_000_000,
+        100_000_000_000_000..=99_999_999_999_999_999 => 1_000,
+        100_000_000_000_000_000..=99_999_999_999_999_999_999 => 1,
+        _ => return Err("the timestamp is too large".to_string()),
+    };
+
+    if !fraction.is_empty() && nanos_per_unit != NANOS_PER_SECOND {
+        return Err("fractional timestamps must be in seconds".to_string());
+    }
+    if fraction.len() > 9 {
+        return Err("the timestamp is finer than nanoseconds".to_string());
+    }
+    let fraction_nanos: i128 = if fraction.is_empty() {
+        0
+    } else {
+        format!("{fraction:0<9}")
+            .parse()
+            .map_err(|_| "the fraction is not a number".to_string())?
+    };
+
+    let mut nanos = integer * nanos_per_unit + fraction_nanos;
+    if negative {
+        nanos = -nanos;
+    }
+    let seconds = i64::try_from(nanos.div_euclid(NANOS_PER_SECOND))
+        .map_err(|_| "the timestamp is too large".to_string())?;
+    let fraction = format!("{:09}", nanos.rem_euclid(NANOS_PER_SECOND));
+
+    Ok(ParsedDateTime {
+        days: seconds.div_euclid(SECONDS_PER_DAY),
+        seconds: seconds.rem_euclid(SECONDS_PER_DAY),
+        fraction,
+        offset: Some(0),
+    })
+}
+
+/// `Z`, `+hh`, `+hhmm` or `+hh:mm` (or with `-`), in seconds east of UTC.
+fn parse_offset(offset: &str) -> Result<i64, String> {
+    if offset.eq_ignore_ascii_case("z") {
+        return Ok(0);
+    }
+
+    let (sign, digits) = match offset.split_at(1) {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return Err("the UTC offset must start with + or -".to_string()),
+    };
+    let digits = digits.replacen(':', "", 1);
+    let (hours, minutes) = match digits.len() {
+        2 => (parse_field(&digits, 2, "offset hour")?, 0),
+        4 => (
+            parse_field(&digits[..2], 2, "offset hour")?,
+            parse_field(&digits[2..], 2, "offset minute")?,
+        ),
+        _ => return Err("the UTC offset must look like +hh:mm".to_string()),
+    };
+    if hours > 14 || minutes > 59 {
+        return Err(format!("`{offset}` is not a valid UTC offset"));
+    }
+
+    Ok(sign * (hours * 3600 + minutes * 60))
+}
+
+/// `hh:mm`, `hh:mm:ss` or `hh:mm:ss.fff`, as seconds into the day and the
+/// fractional digits.
+fn parse_clock(clock: &str) -> Result<(i64, String), String> {
+    let (clock, fraction) = clock.split_once('.').unwrap_or((clock, ""));
+    if !fraction.is_empty() && !is_digits(fraction) {
+        return Err("the fractional seconds must be digits".to_string());
+    }
+    if fraction.len() > 9 {
+        return Err("the time is finer than nanoseconds".to_string());
+    }
+
+    let fields: Vec<&str> = clock.split(':').collect();
+    let (hour, minute, second) = match fields.as_slice() {
+        [hour, minute] if fraction.is_empty() => (*hour, *minute, "00"),
+        [hour, minute, second] => (*hour, *minute, *second),
+        _ => return Err("the time must look like hh:mm:ss".to_string()),
+    };
+    let hour = parse_field(hour, 2, "hour")?;
+    let minute = parse_field(minute, 2, "minute")?;
+    let second = parse_field(second, 2, "second")?;
+    if hour > 23 || minute > 59 || second > 59 {
+        return Err(format!("`{clock}` is not a valid time of day"));
+    }
+
+    Ok((hour * 3600 + minute * 60 + second, fraction.to_string()))
+}
+
+/// `YYYY-MM-DD`, optionally followed by a `T` or space and a time, which may
+/// end in a UTC offset.
+fn parse_calendar(value: &str) -> Result<ParsedDateTime, String> {
+    let (date, time) = match value.split_once(['T', 't', ' ']) {
+        Some((date, time)) => (date, Some(time.trim())),
+        None => (value, None),
+    };
+
+    let fields: Vec<&str> = date.split('-').collect();
+    let [year, month, day] = fields.as_slice() else {
+        return Err("the date must look like YYYY-MM-DD".to_string());
+    };
+    let year = parse_field(year, 4, "year")?;
+    let month = parse_field(month, 2, "month")?;
+    let day = parse_field(day, 2, "day")?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return Err(format!("`{date}` is not a calendar date"));
+    }
+
+    let (seconds, fraction, offset) = match time {
+        None => (0, String::new(), None),
+        Some(time) => {
+            let offset_start = time.find(['Z', 'z', '+', '-']);
+            let (clock, offset) = match offset_start {
+                Some(start) => (&time[..start], Some(parse_offset(&time[start..])?)),
+                None => (time, None),
+            };
+            let (seconds, fraction) = parse_clock(clock.trim_end())?;
+            (seconds, fraction, offset)
+        }
+    };
+
+    Ok(ParsedDateTime {
+        days: days_from_civil(year, month, day),
+        seconds,
+        fraction,
+        offset,
+    })
+}
+
+fn parse_value(value: &str) -> Result<ParsedDateTime, String> {
+    if value.is_empty() {
+        return Err("the value is empty".to_string());
+    }
+
+    let unsigned = value.strip_prefix('-').unwrap_or(value);
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if is_digits(integer) && (fraction.is_empty() || is_digits(fraction)) {
+        return parse_epoch(value);
+    }
+
+    parse_calendar(value)
+}
+
+/// Pads or trims fractional digits to `precision`, refusing to drop any that
+/// are not zero since that would change which rows a filter matches.
+fn fit_fraction(fraction: &str, precision: usize) -> Option<String> {
+    let significant = fraction.trim_end_matches('0');
+    if significant.len() > precision {
+        return None;
+    }
+
+    Some(format!("{significant:0<precision$}"))
+}
+
+fn format_date(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Turns a user-entered date or time into a literal for comparing against a
+/// column of `target_type`. Values without an offset are wall-clock times
+/// and become plain strings, which ClickHouse reads in the column's own
+/// timezone. Timestamps and values with an offset name an instant, so they
+/// are converted to UTC and wrapped in `toDateTime(..., 'UTC')`; for `Date`
+/// columns the day is taken as written.
+pub(crate) fn normalize(value: &str, target_type: &str) -> Result<String, HousecatError> {
+    let target = DateTarget::parse(target_type).ok_or_else(|| {
+        HousecatError::invalid_input(format!(
+            "`{target_type}` is not a Date, Date32, DateTime or DateTime64 type"
+        ))
+    })?;
+    let invalid = |reason: String| HousecatError::InvalidDateTime {
+        value: value.to_string(),
+        reason,
+    };
+    let parsed = parse_value(value.trim()).map_err(invalid)?;
+    let type_name = base_type(target_type);
+
+    let precision = match target {
+        DateTarget::Date | DateTarget::Date32 => {
+            if parsed.seconds != 0 || fit_fraction(&parsed.fraction, 0).is_none() {
+                return Err(invalid(format!("{type_name} has no time of day")));
+            }
+            if !target.contains(parsed.days, 0) {
+                return Err(invalid(format!("it is outside the range of {type_name}")));
+            }
+            return Ok(quote_string_literal(&format_date(parsed.days)));
+        }
+        DateTarget::DateTime => 0,
+        DateTarget::DateTime64(precision) => precision,
+    };
+
+    let fraction = fit_fraction(&parsed.fraction, precision).ok_or_else(|| {
+        invalid(match precision {
+            0 => format!("{type_name} has no fractional seconds"),
+            _ => format!("{type_name} keeps only {precision} fractional digits"),
+        })
+    })?;
+    let (days, seconds) = match parsed.offset {
+        Some(offset) => {
+            let timestamp = parsed.days * SECONDS_PER_DAY + parsed.seconds - offset;
+            (
+                timestamp.div_euclid(SECONDS_PER_DAY),
+                timestamp.rem_euclid(SECONDS_PER_DAY),
+            )
+        }
+        None => (parsed.days, parsed.seconds),
+    };
+    if !target.contains(days, seconds) {
+        return Err(invalid(format!("it is outside the range of {type_name}")));
+    }
+
+    let mut text = format!(
+        "{} {:02}:{:02}:{:02}",
+        format_date(days),
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    if !fraction.is_empty() {
+        text.push('.');
+        text.push_str(&fraction);
+    }
+    let literal = quote_string_literal(&text);
+
+    Ok(match (parsed.offset, target) {
+        (None, _) => literal,
+        (Some(_), DateTarget::DateTime64(precision)) => {
+            format!("toDateTime64({literal}, {precision}, 'UTC')")
+        }
+        (Some(_), _) => format!("toDateTime({literal}, 'UTC')"),
+    })
+}
+
+/// Accepts ISO 8601 dates and times, `YYYY-MM-DD hh:mm:ss` and Unix
+/// timestamps, and returns the ClickHouse literal to filter a
+/// `target_type` column with.
+#[tauri::command]
+pub(crate) fn normalize_datetime(
+    value: String,
+    target_type: String,
+) -> Result<String, HousecatError> {
+    normalize(&value, &target_type)
+}
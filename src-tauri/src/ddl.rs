@@ -0,0 +1,127 @@
+use crate::error::HousecatError;
+use crate::schema::{ensure_table_absent, fetch_column_type};
+use crate::statement::validate_expression;
+use crate::{
+    escape_identifier, qualified_table, quote_string_literal, require_confirmation, require_value,
+    run_clickhouse_query, ClickHouseConnectionInput,
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RenameTableInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    old_name: String,
+    new_name: String,
+    confirmation: String,
+}
+
+/// Renames a table within its database. The confirmation must repeat the
+/// current table name.
+#[tauri::command]
+pub(crate) async fn rename_table(input: RenameTableInput) -> Result<(), HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let old_name = require_value(&input.old_name, "Table")?;
+    let new_name = require_value(&input.new_name, "New table name")?;
+    require_confirmation(&input.confirmation, old_name)?;
+
+    if old_name == new_name {
+        return Err(HousecatError::invalid_input(
+            "The new table name matches the current one",
+        ));
+    }
+
+    ensure_table_absent(&input.connection, schema, new_name).await?;
+
+    let query = format!(
+        "RENAME TABLE {} TO {}",
+        qualified_table(schema, old_name),
+        qualified_table(schema, new_name)
+    );
+
+    run_clickhouse_query(&input.connection, query).await?;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AddColumnInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+    column_name: String,
+    column_type: String,
+    default_expression: Option<String>,
+    confirmation: String,
+}
+
+/// Has the server parse `column_type` on its own, passed as a string
+/// literal, so only a complete, valid type name is ever spliced into DDL.
+async fn validate_column_type(
+    connection: &ClickHouseConnectionInput,
+    column_type: &str,
+) -> Result<(), HousecatError> {
+    // The type parser skips comments, which would then hide the rest of
+    // the ALTER statement.
+    if ["--", "/*", "#", ";"]
+        .iter()
+        .any(|token| column_type.contains(token))
+    {
+        return Err(HousecatError::invalid_input(
+            "Column types cannot contain comments or `;`",
+        ));
+    }
+
+    let query = format!(
+        "SELECT toTypeName(defaultValueOfTypeName({})) AS type FORMAT JSON",
+        quote_string_literal(column_type)
+    );
+
+    match run_clickhouse_query(connection, query).await {
+        Ok(_) => Ok(()),
+        Err(HousecatError::ClickHouse { .. }) => Err(HousecatError::invalid_input(format!(
+            "`{column_type}` is not a valid ClickHouse type"
+        ))),
+        Err(err) => Err(err),
+    }
+}
+
+/// Adds a column to a table, optionally with a `DEFAULT` expression. The
+/// confirmation must repeat the table name.
+#[tauri::command]
+pub(crate) async fn add_column(input: AddColumnInput) -> Result<(), HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+    let column_name = require_value(&input.column_name, "Column name")?;
+    let column_type = require_value(&input.column_type, "Column type")?;
+    require_confirmation(&input.confirmation, table)?;
+
+    let default_clause = match input.default_expression.as_deref().map(str::trim) {
+        Some(expression) if !expression.is_empty() => format!(
+            " DEFAULT {}",
+            validate_expression(expression, "default expression")?
+        ),
+        _ => String::new(),
+    };
+
+    validate_column_type(&input.connection, column_type).await?;
+
+    if fetch_column_type(&input.connection, schema, table, column_name)
+        .await?
+        .is_some()
+    {
+        return Err(HousecatError::ColumnAlreadyExists(format!(
+            "{schema}.{table}.{column_name}"
+        )));
+    }
+
+    let query = format!(
+        "ALTER TABLE {} ADD COLUMN `{}` {column_type}{default_clause}",
+        qualified_table(schema, table),
+        escape_identifier(column_name)
+    );
+
+    run_clickhouse_query(&input.connection, query).await?;
+    Ok(())
+}
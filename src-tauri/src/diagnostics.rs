@@ -0,0 +1,331 @@
+use crate::error::HousecatError;
+use crate::{
+    build_client, fetch_rows, parse_preview_body, prepare_query, require_value,
+    run_clickhouse_query_on, run_clickhouse_query_with_params, statement, tunnels, version,
+    ClickHouseConnectionInput, ColumnInfo, TablePreview, QUOTE_DENORMALS_SETTING,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::net::{lookup_host, TcpStream};
+
+const PROBE_QUERY: &str = "SELECT 1";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ConnectionDiagnostics {
+    /// The host DNS and TCP were probed against: the SSH host for a
+    /// tunnelled connection, which cannot reach the server directly.
+    probed_host: String,
+    resolved_addresses: Vec<String>,
+    dns_ms: f64,
+    tcp_connect_ms: f64,
+    /// Time to open the SSH tunnel; near zero when it was already open, and
+    /// `None` without one.
+    ssh_tunnel_ms: Option<f64>,
+    /// Estimated, not measured directly; `None` for plain HTTP.
+    tls_handshake_ms: Option<f64>,
+    first_query_ms: f64,
+    warm_query_ms: f64,
+}
+
+fn elapsed_ms(started: Instant) -> f64 {
+    started.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Times each phase of reaching the server. DNS and TCP are probed directly,
+/// against the SSH host when the connection is tunnelled; the TLS handshake
+/// is estimated as the cost of a query on a cold client beyond a warm one,
+/// minus the DNS and TCP time already measured when those were the
+/// server's.
+#[tauri::command]
+pub(crate) async fn diagnose_connection(
+    input: ClickHouseConnectionInput,
+) -> Result<ConnectionDiagnostics, HousecatError> {
+    let (host, port) = match tunnels::ssh_endpoint(&input) {
+        Some((ssh_host, ssh_port)) => (require_value(ssh_host, "SSH host")?, ssh_port),
+        None => (require_value(&input.host, "Host")?, input.port),
+    };
+    let connect_timeout = Duration::from_secs(input.connect_timeout().max(1));
+
+    let started = Instant::now();
+    let addresses: Vec<_> = lookup_host((host, port))
+        .await
+        .map_err(|err| HousecatError::Connection(format!("DNS lookup failed: {err}")))?
+        .collect();
+    let dns_ms = elapsed_ms(started);
+
+    let address = addresses
+        .first()
+        .ok_or_else(|| HousecatError::Connection(format!("{host} did not resolve")))?;
+
+    let started = Instant::now();
+    tokio::time::timeout(connect_timeout, TcpStream::connect(address))
+        .await
+        .map_err(|_| HousecatError::Connection(format!("TCP connect to {address} timed out")))?
+        .map_err(|err| HousecatError::Connection(format!("TCP connect failed: {err}")))?;
+    let tcp_connect_ms = elapsed_ms(started);
+
+    let started = Instant::now();
+    tunnels::ensure(&input).await?;
+    let ssh_tunnel_ms = input.ssh_tunnel.is_some().then(|| elapsed_ms(started));
+    let client = build_client(&input)?;
+
+    let started = Instant::now();
+    run_clickhouse_query_on(&client, &input, PROBE_QUERY.to_string(), &[]).await?;
+    let first_query_ms = elapsed_ms(started);
+
+    let started = Instant::now();
+    run_clickhouse_query_on(&client, &input, PROBE_QUERY.to_string(), &[]).await?;
+    let warm_query_ms = elapsed_ms(started);
+
+    let direct_ms = if ssh_tunnel_ms.is_some() {
+        0.0
+    } else {
+        dns_ms + tcp_connect_ms
+    };
+    let tls_handshake_ms = input
+        .secure
+        .then(|| (first_query_ms - warm_query_ms - direct_ms).max(0.0));
+
+    Ok(ConnectionDiagnostics {
+        probed_host: format!("{host}:{port}"),
+        resolved_addresses: addresses.iter().map(ToString::to_string).collect(),
+        dns_ms,
+        tcp_connect_ms,
+        ssh_tunnel_ms,
+        tls_handshake_ms,
+        first_query_ms,
+        warm_query_ms,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseNowRow {
+    now: String,
+    timezone: String,
+    unix_ms: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ServerTime {
+    /// `now64(3)` rendered in the server's timezone.
+    now: String,
+    timezone: String,
+    unix_ms: i64,
+    /// Server clock minus local clock, assuming the request took as long to
+    /// arrive as the response did.
+    skew_ms: i64,
+}
+
+fn unix_ms_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// The server's current time and timezone, for building relative ranges
+/// such as "last 24 hours" against server time instead of local time.
+#[tauri::command]
+pub(crate) async fn server_now(
+    input: ClickHouseConnectionInput,
+) -> Result<ServerTime, HousecatError> {
+    let sent_ms = unix_ms_now();
+    let row = fetch_rows::<ClickHouseNowRow>(
+        &input,
+        "WITH now64(3) AS current SELECT toString(current) AS now, timezone() AS timezone, toUnixTimestamp64Milli(current) AS unix_ms FORMAT JSON"
+            .to_string(),
+    )
+    .await?
+    .into_iter()
+    .next()
+    .ok_or_else(|| HousecatError::Response("no time row returned".to_string()))?;
+    let received_ms = unix_ms_now();
+
+    Ok(ServerTime {
+        skew_ms: row.unix_ms - (sent_ms + received_ms) / 2,
+        now: row.now,
+        timezone: row.timezone,
+        unix_ms: row.unix_ms,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueryDebugInput {
+    connection: ClickHouseConnectionInput,
+    query: String,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueryDebugResult {
+    status: u16,
+    /// Header names are lowercase; repeated headers are joined with `, `.
+    headers: BTreeMap<String, String>,
+    result: TablePreview,
+}
+
+/// Headers whose values could carry credentials or session tokens.
+const REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-clickhouse-key",
+];
+
+fn collect_headers(headers: &reqwest::header::HeaderMap) -> BTreeMap<String, String> {
+    let mut collected: BTreeMap<String, String> = BTreeMap::new();
+    for (name, value) in headers {
+        let value = if REDACTED_HEADERS.contains(&name.as_str()) {
+            "[redacted]".to_string()
+        } else {
+            String::from_utf8_lossy(value.as_bytes()).into_owned()
+        };
+
+        collected
+            .entry(name.as_str().to_string())
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(&value);
+            })
+            .or_insert(value);
+    }
+
+    collected
+}
+
+/// Runs a query like the editor does and returns the HTTP status and
+/// response headers alongside the rows, including `X-ClickHouse-Summary`,
+/// `X-ClickHouse-Query-Id` and `X-ClickHouse-Server-Display-Name`, which
+/// other commands discard. Failed queries still return the usual error.
+#[tauri::command]
+pub(crate) async fn run_query_debug(
+    input: QueryDebugInput,
+) -> Result<QueryDebugResult, HousecatError> {
+    statement::require_allowed_on_connection(&input.query, &input.connection)?;
+    let limit = input.limit.unwrap_or(500).clamp(1, 10_000);
+    let query = prepare_query(&input.query, Some(limit), "JSON")?;
+
+    let response = run_clickhouse_query_with_params(
+        &input.connection,
+        query,
+        &[(QUOTE_DENORMALS_SETTING, "1".to_string())],
+    )
+    .await?;
+    let status = response.status().as_u16();
+    let headers = collect_headers(response.headers());
+    let body = response
+        .text()
+        .await
+        .map_err(|err| HousecatError::Response(err.to_string()))?;
+
+    Ok(QueryDebugResult {
+        status,
+        headers,
+        result: parse_preview_body(&body),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReproBundleInput {
+    connection: ClickHouseConnectionInput,
+    query: String,
+    limit: Option<u32>,
+}
+
+/// The parts of a connection needed to reproduce against the same server.
+/// The password, session id and certificates are left out; only whether a
+/// private CA or a client certificate was configured is kept.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SanitizedConnection<'a> {
+    host: &'a str,
+    port: u16,
+    username: &'a str,
+    database: Option<&'a str>,
+    secure: bool,
+    read_only: bool,
+    connect_timeout_secs: u64,
+    read_timeout_secs: u64,
+    accept_invalid_certs: bool,
+    custom_ca_cert: bool,
+    client_cert: bool,
+    ssh_tunnel: bool,
+}
+
+impl<'a> From<&'a ClickHouseConnectionInput> for SanitizedConnection<'a> {
+    fn from(connection: &'a ClickHouseConnectionInput) -> Self {
+        Self {
+            host: connection.host.trim(),
+            port: connection.port,
+            username: connection.username.trim(),
+            database: connection.database.as_deref(),
+            secure: connection.secure,
+            read_only: connection.read_only.unwrap_or(false),
+            connect_timeout_secs: connection.connect_timeout(),
+            read_timeout_secs: connection.read_timeout(),
+            accept_invalid_certs: connection.accepts_invalid_certs(),
+            custom_ca_cert: connection.has_private_ca(),
+            client_cert: connection.client_cert_path().is_some(),
+            ssh_tunnel: connection.ssh_tunnel.is_some(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReproBundle<'a> {
+    app_version: &'static str,
+    server_version: String,
+    connection: SanitizedConnection<'a>,
+    query: &'a str,
+    /// The statement as sent to ClickHouse, after the row limit and
+    /// `FORMAT` were added.
+    final_sql: &'a str,
+    settings: BTreeMap<&'a str, String>,
+    columns: Vec<ColumnInfo>,
+    /// Rows returned, at most `limit`.
+    row_count: usize,
+    limit: u32,
+}
+
+/// Runs a read-only query and packages what a teammate or bug report needs
+/// to reproduce it: the server version, the connection without any
+/// credentials, the query, the exact SQL sent and the result's shape. The
+/// rows themselves are not included. Returns pretty-printed JSON.
+#[tauri::command]
+pub(crate) async fn export_repro_bundle(input: ReproBundleInput) -> Result<String, HousecatError> {
+    statement::require_read_only(&input.query)?;
+    let limit = input.limit.unwrap_or(500).clamp(1, 10_000);
+    let final_sql = prepare_query(&input.query, Some(limit), "JSON")?;
+    let params = [(QUOTE_DENORMALS_SETTING, "1".to_string())];
+
+    let server_version = version::server_version(&input.connection).await?;
+    let body = run_clickhouse_query_with_params(&input.connection, final_sql.clone(), &params)
+        .await?
+        .text()
+        .await
+        .map_err(|err| HousecatError::Response(err.to_string()))?;
+    let result = parse_preview_body(&body);
+
+    let bundle = ReproBundle {
+        app_version: env!("CARGO_PKG_VERSION"),
+        server_version,
+        connection: SanitizedConnection::from(&input.connection),
+        query: input.query.trim(),
+        final_sql: &final_sql,
+        settings: params.into_iter().collect(),
+        columns: result.columns,
+        row_count: result.rows.len(),
+        limit,
+    };
+
+    serde_json::to_string_pretty(&bundle)
+        .map_err(|err| HousecatError::Io(format!("Could not serialize repro bundle: {err}")))
+}
@@ -0,0 +1,96 @@
+use crate::error::HousecatError;
+use crate::{fetch_rows, ClickHouseConnectionInput};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseDictionaryRow {
+    database: String,
+    name: String,
+    status: String,
+    layout: String,
+    source: String,
+    key_names: Vec<String>,
+    key_types: Vec<String>,
+    attribute_names: Vec<String>,
+    attribute_types: Vec<String>,
+    lifetime_min: u64,
+    lifetime_max: u64,
+    last_exception: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DictionaryField {
+    name: String,
+    data_type: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DictionarySource {
+    database: String,
+    name: String,
+    status: String,
+    layout: String,
+    /// The source kind, such as `ClickHouse`, `MySQL` or `HTTP`, taken from
+    /// the server's description of the source.
+    source_type: String,
+    /// The server's full description of the source, e.g.
+    /// `ClickHouse: default.users`. Credentials are never included.
+    source: String,
+    keys: Vec<DictionaryField>,
+    attributes: Vec<DictionaryField>,
+    lifetime_min: u64,
+    lifetime_max: u64,
+    last_exception: Option<String>,
+}
+
+fn fields(names: Vec<String>, types: Vec<String>) -> Vec<DictionaryField> {
+    names
+        .into_iter()
+        .zip(types)
+        .map(|(name, data_type)| DictionaryField { name, data_type })
+        .collect()
+}
+
+/// How each dictionary on the server is configured: its source, layout,
+/// keys and attributes, read from `system.dictionaries` so the XML or DDL
+/// on the server never has to be opened.
+#[tauri::command]
+pub(crate) async fn fetch_dictionary_sources(
+    input: ClickHouseConnectionInput,
+) -> Result<Vec<DictionarySource>, HousecatError> {
+    let query = "SELECT database, name, toString(status) AS status, type AS layout, source, \
+        `key.names` AS key_names, `key.types` AS key_types, \
+        `attribute.names` AS attribute_names, `attribute.types` AS attribute_types, \
+        lifetime_min, lifetime_max, last_exception \
+        FROM system.dictionaries ORDER BY database, name FORMAT JSON";
+
+    let rows: Vec<ClickHouseDictionaryRow> = fetch_rows(&input, query.to_string()).await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let source_type = row
+                .source
+                .split_once(':')
+                .map_or(row.source.as_str(), |(kind, _)| kind)
+                .trim()
+                .to_string();
+
+            DictionarySource {
+                database: row.database,
+                name: row.name,
+                status: row.status,
+                layout: row.layout,
+                source_type,
+                source: row.source,
+                keys: fields(row.key_names, row.key_types),
+                attributes: fields(row.attribute_names, row.attribute_types),
+                lifetime_min: row.lifetime_min,
+                lifetime_max: row.lifetime_max,
+                last_exception: Some(row.last_exception).filter(|err| !err.is_empty()),
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,159 @@
+use crate::error::HousecatError;
+use crate::{
+    base_type, escape_identifier, fetch_rows_with_params, statement, ClickHouseConnectionInput,
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Locales numbers can be grouped for, with their thousands separator and
+/// decimal mark. French and Russian group with a narrow no-break space.
+const NUMBER_LOCALES: &[(&str, &str, &str)] = &[
+    ("en-US", ",", "."),
+    ("en-GB", ",", "."),
+    ("de-DE", ".", ","),
+    ("de-CH", "'", "."),
+    ("es-ES", ".", ","),
+    ("fr-FR", "\u{202f}", ","),
+    ("it-IT", ".", ","),
+    ("ja-JP", ",", "."),
+    ("nl-NL", ".", ","),
+    ("pt-BR", ".", ","),
+    ("ru-RU", "\u{202f}", ","),
+    ("zh-CN", ",", "."),
+];
+
+/// How numbers are turned into display strings. Without a locale they are
+/// kept raw, with no grouping, so the strings still parse as numbers.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct NumberFormat {
+    separators: Option<(&'static str, &'static str)>,
+}
+
+impl NumberFormat {
+    pub(crate) fn for_locale(locale: Option<&str>) -> Result<Self, HousecatError> {
+        let Some(locale) = locale.map(str::trim).filter(|locale| !locale.is_empty()) else {
+            return Ok(Self::default());
+        };
+
+        NUMBER_LOCALES
+            .iter()
+            .find(|(name, _, _)| name.eq_ignore_ascii_case(locale))
+            .map(|(_, group, decimal)| Self {
+                separators: Some((group, decimal)),
+            })
+            .ok_or_else(|| {
+                HousecatError::invalid_input(format!("Unsupported number locale `{locale}`"))
+            })
+    }
+
+    fn format(&self, number: &str) -> String {
+        let Some((group, decimal)) = self.separators else {
+            return number.to_string();
+        };
+
+        // Exponent notation is left alone; grouping its mantissa would only
+        // make it harder to read.
+        if number.contains(['e', 'E']) {
+            return number.to_string();
+        }
+
+        let (sign, unsigned) = match number.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", number),
+        };
+        let (integer, fraction) = match unsigned.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (unsigned, None),
+        };
+
+        let mut grouped = String::new();
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                grouped.push_str(group);
+            }
+            grouped.push(digit);
+        }
+
+        match fraction {
+            Some(fraction) => format!("{sign}{grouped}{decimal}{fraction}"),
+            None => format!("{sign}{grouped}"),
+        }
+    }
+
+    /// Replaces every number in `value`, including those nested in arrays
+    /// and objects, with its display string.
+    pub(crate) fn stringify(&self, value: &mut Value) {
+        match value {
+            Value::Number(number) => *value = Value::String(self.format(&number.to_string())),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.stringify(item)),
+            Value::Object(entries) => entries.values_mut().for_each(|item| self.stringify(item)),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseDescribeRow {
+    name: String,
+    #[serde(rename = "type")]
+    data_type: String,
+}
+
+/// Decimals arrive as JSON numbers and lose digits to float parsing;
+/// `DateTime64` is padded to its full precision. Both read better as the
+/// strings ClickHouse itself would print.
+fn needs_display_coercion(data_type: &str) -> bool {
+    let base = base_type(data_type);
+    base.starts_with("Decimal") || base.starts_with("DateTime64")
+}
+
+/// Rewrites a `SELECT` so its decimal and `DateTime64` columns come back as
+/// `toString()` text, after probing the result types with `DESCRIBE`. Other
+/// statements, and selects with nothing to coerce, are returned unchanged.
+pub(crate) async fn coerce_display_query(
+    connection: &ClickHouseConnectionInput,
+    raw_query: &str,
+    params: &[(&str, String)],
+) -> Result<String, HousecatError> {
+    let query = statement::clean_statement(raw_query)?;
+    if !matches!(
+        statement::leading_keyword(query).as_str(),
+        "SELECT" | "WITH"
+    ) {
+        return Ok(query.to_string());
+    }
+
+    if query.to_uppercase().contains("FORMAT ") {
+        return Err(HousecatError::invalid_input(
+            "coerceDisplay cannot be combined with an explicit FORMAT",
+        ));
+    }
+
+    let columns: Vec<ClickHouseDescribeRow> = fetch_rows_with_params(
+        connection,
+        format!("DESCRIBE ({query}) FORMAT JSON"),
+        params,
+    )
+    .await?;
+
+    if !columns
+        .iter()
+        .any(|column| needs_display_coercion(&column.data_type))
+    {
+        return Ok(query.to_string());
+    }
+
+    let select_list: Vec<String> = columns
+        .iter()
+        .map(|column| {
+            let name = escape_identifier(&column.name);
+            if needs_display_coercion(&column.data_type) {
+                format!("toString(`{name}`) AS `{name}`")
+            } else {
+                format!("`{name}`")
+            }
+        })
+        .collect();
+
+    Ok(format!("SELECT {} FROM ({query})", select_list.join(", ")))
+}
@@ -0,0 +1,177 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+/// ClickHouse exception codes the backend reacts to.
+pub(crate) const UNKNOWN_TABLE: u32 = 60;
+pub(crate) const SESSION_NOT_FOUND: u32 = 372;
+const TABLE_ALREADY_EXISTS: u32 = 57;
+const SET_SIZE_LIMIT_EXCEEDED: u32 = 191;
+const READONLY: u32 = 164;
+const DUPLICATE_COLUMN: u32 = 15;
+const ACCESS_DENIED: u32 = 497;
+const TIMEOUT_EXCEEDED: u32 = 159;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum HousecatError {
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("`{value}` is not a valid date or time: {reason}")]
+    InvalidDateTime { value: String, reason: String },
+    #[error("Confirmation did not match `{expected}`")]
+    ConfirmationMismatch { expected: String },
+    #[error("Could not initialize ClickHouse client: {0}")]
+    Client(String),
+    #[error("Could not connect to ClickHouse: {0}")]
+    Connection(String),
+    #[error("ClickHouse returned {status}: {body}")]
+    ClickHouse {
+        status: u16,
+        code: Option<u32>,
+        body: String,
+    },
+    #[error("The JOIN exceeded its size limit. Try a smaller right-hand table, a different join_algorithm or a higher max_bytes_in_join: {0}")]
+    JoinTooLarge(String),
+    #[error("Table {0} already exists")]
+    TableAlreadyExists(String),
+    #[error("Column {0} already exists")]
+    ColumnAlreadyExists(String),
+    #[error("Tables are not compatible: {0}")]
+    IncompatibleTables(String),
+    #[error("Query {0} is not in system.query_log yet; the log is flushed every few seconds")]
+    QueryNotLogged(String),
+    #[error("Copy stopped after {rows_copied} rows: {reason}")]
+    CopyInterrupted { rows_copied: u64, reason: String },
+    #[error("Import stopped after {rows_imported} rows: {reason}")]
+    ImportInterrupted { rows_imported: u64, reason: String },
+    #[error("The connection is read-only: {0}")]
+    ReadOnly(String),
+    #[error("Query {0} is not running")]
+    QueryNotRunning(String),
+    #[error("Query did not finish within its {0} ms deadline")]
+    DeadlineExceeded(u64),
+    #[error("Could not read ClickHouse response: {0}")]
+    Response(String),
+    #[error("Could not parse ClickHouse response: {0}")]
+    Parse(String),
+    #[error("{0}")]
+    Io(String),
+    #[error("Cannot export here: {0}")]
+    PathNotWritable(String),
+    #[error("{0} was cancelled")]
+    Cancelled(String),
+    #[error("{0} is not available on this server")]
+    Unavailable(String),
+    #[error("The current user is not allowed to read {0}")]
+    AccessDenied(String),
+    #[error("Could not access the system keychain: {0}")]
+    Keychain(String),
+    #[error("Saved connection {0} does not exist")]
+    ConnectionNotFound(String),
+}
+
+impl HousecatError {
+    pub(crate) fn invalid_input(message: impl Into<String>) -> Self {
+        Self::InvalidInput(message.into())
+    }
+
+    /// Builds the error for a non-success response, promoting exceptions the
+    /// UI handles specially to their own variants.
+    pub(crate) fn from_response(status: u16, body: String) -> Self {
+        let code = parse_exception_code(&body);
+
+        match code {
+            Some(SET_SIZE_LIMIT_EXCEEDED) if body.contains("JOIN") => Self::JoinTooLarge(body),
+            Some(TABLE_ALREADY_EXISTS) => Self::TableAlreadyExists(body),
+            Some(READONLY) => Self::ReadOnly(body),
+            Some(DUPLICATE_COLUMN) => Self::ColumnAlreadyExists(body),
+            _ => Self::ClickHouse { status, code, body },
+        }
+    }
+
+    /// Reports a missing optional system table, such as `system.query_log`
+    /// on servers where query logging is disabled, as [`Self::Unavailable`].
+    pub(crate) fn missing_table_as_unavailable(self, table: &str) -> Self {
+        if self.clickhouse_code() == Some(UNKNOWN_TABLE) {
+            return Self::Unavailable(table.to_string());
+        }
+
+        self
+    }
+
+    /// Reports a system table the current user may not read, such as the
+    /// access-management tables, as [`Self::AccessDenied`].
+    pub(crate) fn access_denied_as(self, table: &str) -> Self {
+        if self.clickhouse_code() == Some(ACCESS_DENIED) {
+            return Self::AccessDenied(table.to_string());
+        }
+
+        self
+    }
+
+    /// Whether the server stopped the query at its `max_execution_time`,
+    /// either before responding or in the middle of a streamed body.
+    pub(crate) fn is_timeout_exceeded(&self) -> bool {
+        match self {
+            Self::ClickHouse { code, .. } => *code == Some(TIMEOUT_EXCEEDED),
+            Self::Response(body) => parse_exception_code(body) == Some(TIMEOUT_EXCEEDED),
+            _ => false,
+        }
+    }
+
+    /// The ClickHouse exception code, when the server reported one.
+    pub(crate) fn clickhouse_code(&self) -> Option<u32> {
+        match self {
+            Self::ClickHouse { code, .. } => *code,
+            _ => None,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::InvalidInput(_) => "invalidInput",
+            Self::InvalidDateTime { .. } => "invalidDateTime",
+            Self::ConfirmationMismatch { .. } => "confirmationMismatch",
+            Self::Client(_) => "client",
+            Self::Connection(_) => "connection",
+            Self::ClickHouse { .. } => "clickHouse",
+            Self::JoinTooLarge(_) => "joinTooLarge",
+            Self::TableAlreadyExists(_) => "tableAlreadyExists",
+            Self::ColumnAlreadyExists(_) => "columnAlreadyExists",
+            Self::IncompatibleTables(_) => "incompatibleTables",
+            Self::QueryNotLogged(_) => "queryNotLogged",
+            Self::CopyInterrupted { .. } => "copyInterrupted",
+            Self::ImportInterrupted { .. } => "importInterrupted",
+            Self::ReadOnly(_) => "readOnly",
+            Self::QueryNotRunning(_) => "queryNotRunning",
+            Self::DeadlineExceeded(_) => "deadlineExceeded",
+            Self::Response(_) => "response",
+            Self::Parse(_) => "parse",
+            Self::Io(_) => "io",
+            Self::PathNotWritable(_) => "pathNotWritable",
+            Self::Cancelled(_) => "cancelled",
+            Self::Unavailable(_) => "unavailable",
+            Self::AccessDenied(_) => "accessDenied",
+            Self::Keychain(_) => "keychain",
+            Self::ConnectionNotFound(_) => "connectionNotFound",
+        }
+    }
+}
+
+/// Errors reach the frontend as `{ kind, message }` so the UI can branch on
+/// the kind while still showing a readable message.
+impl Serialize for HousecatError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("HousecatError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+/// Extracts the numeric exception code from a ClickHouse error body such as
+/// `Code: 60. DB::Exception: Table default.foo does not exist.`
+pub(crate) fn parse_exception_code(body: &str) -> Option<u32> {
+    let rest = &body[body.find("Code: ")? + "Code: ".len()..];
+    let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
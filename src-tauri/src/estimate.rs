@@ -0,0 +1,118 @@
+use crate::error::HousecatError;
+use crate::statement::require_read_only;
+use crate::{fetch_rows, quote_string_literal, ClickHouseConnectionInput};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EstimateInput {
+    connection: ClickHouseConnectionInput,
+    query: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseEstimateRow {
+    database: String,
+    table: String,
+    parts: u64,
+    rows: u64,
+    marks: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseTableSizeRow {
+    database: String,
+    table: String,
+    rows: u64,
+    bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TableMemoryEstimate {
+    database: String,
+    table: String,
+    parts: u64,
+    rows: u64,
+    marks: u64,
+    estimated_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MemoryEstimate {
+    /// Always true; the figure is a heuristic, not a measurement.
+    approximate: bool,
+    estimated_bytes: u64,
+    tables: Vec<TableMemoryEstimate>,
+}
+
+/// Estimates how much data `query` will pull into memory: `EXPLAIN ESTIMATE`
+/// gives the rows each table will read, which are scaled by that table's
+/// average uncompressed row size from `system.parts`. This is an upper bound
+/// on data read rather than peak memory, which aggregations and joins can
+/// push well above or below it.
+#[tauri::command]
+pub(crate) async fn estimate_memory(input: EstimateInput) -> Result<MemoryEstimate, HousecatError> {
+    let query = require_read_only(&input.query)?;
+
+    let estimates: Vec<ClickHouseEstimateRow> = fetch_rows(
+        &input.connection,
+        format!("EXPLAIN ESTIMATE {query} FORMAT JSON"),
+    )
+    .await?;
+
+    if estimates.is_empty() {
+        return Ok(MemoryEstimate {
+            approximate: true,
+            estimated_bytes: 0,
+            tables: Vec::new(),
+        });
+    }
+
+    let table_filter = estimates
+        .iter()
+        .map(|row| {
+            format!(
+                "({}, {})",
+                quote_string_literal(&row.database),
+                quote_string_literal(&row.table)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let sizes: Vec<ClickHouseTableSizeRow> = fetch_rows(
+        &input.connection,
+        format!(
+            "SELECT database, table, sum(rows) AS rows, sum(data_uncompressed_bytes) AS bytes FROM system.parts WHERE active AND (database, table) IN ({table_filter}) GROUP BY database, table FORMAT JSON"
+        ),
+    )
+    .await?;
+
+    let tables: Vec<TableMemoryEstimate> = estimates
+        .into_iter()
+        .map(|row| {
+            let bytes_per_row = sizes
+                .iter()
+                .find(|size| size.database == row.database && size.table == row.table)
+                .filter(|size| size.rows > 0)
+                .map_or(0.0, |size| size.bytes as f64 / size.rows as f64);
+
+            TableMemoryEstimate {
+                estimated_bytes: (row.rows as f64 * bytes_per_row) as u64,
+                database: row.database,
+                table: row.table,
+                parts: row.parts,
+                rows: row.rows,
+                marks: row.marks,
+            }
+        })
+        .collect();
+
+    Ok(MemoryEstimate {
+        approximate: true,
+        estimated_bytes: tables.iter().map(|table| table.estimated_bytes).sum(),
+        tables,
+    })
+}
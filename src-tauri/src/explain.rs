@@ -0,0 +1,186 @@
+use crate::error::parse_exception_code;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ErrorExplanation {
+    code: Option<u32>,
+    /// ClickHouse's symbolic name for the code, such as `UNKNOWN_TABLE`.
+    name: Option<String>,
+    message: String,
+    explanation: String,
+    suggestions: Vec<String>,
+}
+
+/// Explanations and fixes for the exceptions users run into most often,
+/// keyed by ClickHouse error code.
+const ERROR_HINTS: &[(u32, &str, &[&str])] = &[
+    (
+        43,
+        "A function was called with an argument of the wrong type.",
+        &["Check the argument types, or convert them with a toType() function such as toString or toInt64."],
+    ),
+    (
+        46,
+        "The query calls a function the server does not know.",
+        &["Check the spelling; function names are case-sensitive.", "The function may need a newer ClickHouse version."],
+    ),
+    (
+        47,
+        "A column or alias in the query does not exist.",
+        &["Check the column name and its case.", "Qualify the column with its table when joining."],
+    ),
+    (
+        53,
+        "A value's type does not match the type expected here.",
+        &["Cast the value explicitly, for example with CAST(x AS Type)."],
+    ),
+    (
+        57,
+        "A table with that name already exists.",
+        &["Pick another name, or use IF NOT EXISTS."],
+    ),
+    (
+        60,
+        "The table does not exist.",
+        &["Verify the table name and its database.", "Refresh the schema list; the table may have been dropped or renamed."],
+    ),
+    (
+        62,
+        "The query could not be parsed.",
+        &["Check the SQL near the position the message points to.", "Look for unbalanced quotes or parentheses."],
+    ),
+    (
+        81,
+        "The database does not exist.",
+        &["Verify the database name, or pick another default database for the connection."],
+    ),
+    (
+        158,
+        "The query would return more rows than max_result_rows allows.",
+        &["Add a LIMIT or a narrower WHERE clause.", "Raise max_result_rows for this query."],
+    ),
+    (
+        159,
+        "The query ran longer than max_execution_time.",
+        &["Filter on the table's sorting key so less data is read.", "Raise max_execution_time, and the connection's read timeout with it."],
+    ),
+    (
+        164,
+        "The connection is read-only and the query tried to change something.",
+        &["Use a connection whose user may write, or run only SELECT queries."],
+    ),
+    (
+        191,
+        "A set or JOIN grew past its size limit.",
+        &["Put the smaller table on the right-hand side of the JOIN.", "Raise max_bytes_in_join or try a different join_algorithm."],
+    ),
+    (
+        202,
+        "The server is already running as many queries as it allows.",
+        &["Wait for running queries to finish, or kill ones that are stuck."],
+    ),
+    (
+        241,
+        "The query needed more memory than it is allowed to use.",
+        &["Reduce query memory: filter earlier, select fewer columns or aggregate on fewer keys.", "Enable max_bytes_before_external_group_by or max_bytes_before_external_sort to spill to disk.", "Raise max_memory_usage if the server has headroom."],
+    ),
+    (
+        252,
+        "The table has too many active parts, usually from many small inserts.",
+        &["Batch inserts into fewer, larger ones, or enable async_insert.", "Wait for background merges to catch up."],
+    ),
+    (
+        372,
+        "The session expired or was never opened.",
+        &["Open a new session; temporary tables from the old one are gone."],
+    ),
+    (
+        373,
+        "The session is busy with another query.",
+        &["Wait for the other query in this session to finish."],
+    ),
+    (
+        394,
+        "The query was cancelled.",
+        &["Run it again if the cancellation was not intended."],
+    ),
+    (
+        497,
+        "The user lacks a privilege the query needs.",
+        &["Ask an administrator to GRANT the missing privilege shown in the message."],
+    ),
+    (
+        516,
+        "The server rejected the username or password.",
+        &["Check the credentials saved for this connection.", "Make sure the user may connect from this host."],
+    ),
+];
+
+/// The symbolic name ClickHouse appends to messages, as in
+/// `... does not exist. (UNKNOWN_TABLE) (version 23.8.1.2992)`.
+fn exception_name(body: &str) -> Option<String> {
+    let body = match body.rfind(" (version ") {
+        Some(end) => &body[..end],
+        None => body,
+    };
+    let inner = body.trim_end().strip_suffix(')')?;
+    let name = &inner[inner.rfind('(')? + 1..];
+
+    (!name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'))
+    .then(|| name.to_string())
+}
+
+/// The message text without the `Code: N. DB::Exception:` prefix and the
+/// trailing name and version.
+fn exception_message(body: &str, name: Option<&str>) -> String {
+    let mut message = body.trim();
+    if let Some(start) = message.find("DB::Exception: ") {
+        message = &message[start + "DB::Exception: ".len()..];
+    }
+    if let Some(end) = message.rfind(" (version ") {
+        message = &message[..end];
+    }
+    if let Some(name) = name {
+        message = message
+            .trim_end()
+            .strip_suffix(&format!("({name})"))
+            .unwrap_or(message);
+    }
+
+    message.trim().to_string()
+}
+
+pub(crate) fn explain(body: &str) -> ErrorExplanation {
+    let code = parse_exception_code(body);
+    let name = exception_name(body);
+    let message = exception_message(body, name.as_deref());
+    let hint = code.and_then(|code| ERROR_HINTS.iter().find(|(known, _, _)| *known == code));
+
+    let (explanation, suggestions) = match hint {
+        Some((_, explanation, suggestions)) => (
+            explanation.to_string(),
+            suggestions.iter().map(|text| text.to_string()).collect(),
+        ),
+        None => (
+            "ClickHouse could not run the query.".to_string(),
+            Vec::new(),
+        ),
+    };
+
+    ErrorExplanation {
+        code,
+        name,
+        message,
+        explanation,
+        suggestions,
+    }
+}
+
+#[tauri::command]
+pub(crate) fn explain_error(error_body: String) -> ErrorExplanation {
+    explain(&error_body)
+}
@@ -0,0 +1,379 @@
+use crate::error::HousecatError;
+use crate::{
+    require_value, run_clickhouse_query_with_params, statement, ClickHouseConnectionInput,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Notify;
+
+/// Least time between two `export-progress` events of one export.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Lets Excel recognise a CSV file as UTF-8 rather than the system code page.
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Cancellation signals of running exports, keyed by path.
+#[derive(Debug, Default)]
+pub(crate) struct ExportRegistry {
+    exports: Mutex<HashMap<String, Arc<Notify>>>,
+}
+
+impl ExportRegistry {
+    fn start(&self, path: &str) -> Result<Arc<Notify>, HousecatError> {
+        let cancel = Arc::new(Notify::new());
+        if let Ok(mut exports) = self.exports.lock() {
+            if exports.contains_key(path) {
+                return Err(HousecatError::invalid_input(format!(
+                    "An export to {path} is already running"
+                )));
+            }
+            exports.insert(path.to_string(), cancel.clone());
+        }
+
+        Ok(cancel)
+    }
+
+    fn finish(&self, path: &str) {
+        if let Ok(mut exports) = self.exports.lock() {
+            exports.remove(path);
+        }
+    }
+
+    fn cancel(&self, path: &str) -> bool {
+        let cancel = self
+            .exports
+            .lock()
+            .ok()
+            .and_then(|exports| exports.get(path).cloned());
+
+        match cancel {
+            Some(cancel) => {
+                // Stored as a permit if the export is between two waits.
+                cancel.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum ExportFormat {
+    Csv,
+    Tsv,
+    Parquet,
+    JsonEachRow,
+}
+
+impl ExportFormat {
+    fn clickhouse_format(self) -> &'static str {
+        match self {
+            Self::Csv => "CSVWithNames",
+            Self::Tsv => "TSVWithNames",
+            Self::Parquet => "Parquet",
+            Self::JsonEachRow => "JSONEachRow",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExportQueryInput {
+    connection: ClickHouseConnectionInput,
+    query: String,
+    path: String,
+    format: ExportFormat,
+    /// Writes a CSV file the way Excel reads it correctly: with a UTF-8 byte
+    /// order mark and CRLF line endings. Off by default, which keeps plain
+    /// UTF-8.
+    excel_compatible: Option<bool>,
+}
+
+/// Drops a trailing `FORMAT <name>` so the export's own format is the only
+/// one. The query is cut where the lexer found the clause, so text in a
+/// literal or comment is never mistaken for it.
+fn strip_format_clause(query: &str) -> &str {
+    match statement::positioned_tokens(query).as_slice() {
+        [.., (start, keyword), (_, name)]
+            if keyword == "FORMAT"
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+        {
+            query[..*start].trim_end()
+        }
+        _ => query,
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PathValidation {
+    parent_exists: bool,
+    writable: bool,
+    /// Free space for the current user on the parent's filesystem; `None`
+    /// where the platform does not report it.
+    available_bytes: Option<u64>,
+    /// Why the path cannot be exported to, when it cannot.
+    problem: Option<String>,
+}
+
+#[cfg(unix)]
+fn available_bytes(directory: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let directory = std::ffi::CString::new(directory.as_os_str().as_bytes()).ok()?;
+    let mut stats = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `directory` is NUL-terminated and `stats` is only read after
+    // `statvfs` reports that it filled it in.
+    let stats = unsafe {
+        if libc::statvfs(directory.as_ptr(), stats.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stats.assume_init()
+    };
+
+    // The field widths differ between platforms.
+    #[allow(clippy::unnecessary_cast)]
+    Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn available_bytes(_directory: &Path) -> Option<u64> {
+    None
+}
+
+/// Creates and removes a scratch file, since permission bits alone miss
+/// read-only mounts, ACLs and sandboxing.
+fn can_create_files(directory: &Path) -> bool {
+    let probe = directory.join(format!(".housecat-write-test-{}", uuid::Uuid::new_v4()));
+    let created = std::fs::File::create(&probe).is_ok();
+    if created {
+        let _ = std::fs::remove_file(&probe);
+    }
+
+    created
+}
+
+fn check_export_path(path: &str) -> PathValidation {
+    let path = Path::new(path.trim());
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent_exists = parent.is_dir();
+    let writable = parent_exists && can_create_files(parent);
+
+    let problem = if path.as_os_str().is_empty() {
+        Some("Export path is required".to_string())
+    } else if path.is_dir() {
+        Some(format!("{} is a directory", path.display()))
+    } else if !parent_exists {
+        Some(format!("Directory {} does not exist", parent.display()))
+    } else if !writable {
+        Some(format!("Directory {} is not writable", parent.display()))
+    } else {
+        None
+    };
+
+    PathValidation {
+        parent_exists,
+        writable,
+        available_bytes: parent_exists.then(|| available_bytes(parent)).flatten(),
+        problem,
+    }
+}
+
+/// Checks an export destination up front, so a bad path fails before any
+/// data is streamed rather than after.
+#[tauri::command]
+pub(crate) fn validate_export_path(path: String) -> PathValidation {
+    check_export_path(&path)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportProgress<'a> {
+    path: &'a str,
+    bytes_written: u64,
+    done: bool,
+}
+
+/// Awaits `future` unless the export is cancelled first, in which case the
+/// future is dropped along with whatever it was waiting on.
+async fn unless_cancelled<F: Future>(
+    cancel: &Notify,
+    path: &str,
+    future: F,
+) -> Result<F::Output, HousecatError> {
+    let mut cancelled = std::pin::pin!(cancel.notified());
+    let mut future = std::pin::pin!(future);
+
+    std::future::poll_fn(|cx| {
+        if cancelled.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(Err(HousecatError::Cancelled(format!("Export to {path}"))));
+        }
+        future.as_mut().poll(cx).map(Ok)
+    })
+    .await
+}
+
+/// Copies `prefix` and then the response body to `file` chunk by chunk,
+/// returning the bytes written.
+async fn write_body(
+    app: &AppHandle,
+    mut response: reqwest::Response,
+    file: &mut tokio::fs::File,
+    path: &str,
+    prefix: &[u8],
+    cancel: &Notify,
+) -> Result<u64, HousecatError> {
+    let write_error =
+        |err: std::io::Error| HousecatError::Io(format!("Could not write {path}: {err}"));
+    let report = |bytes_written, done| {
+        let _ = app.emit(
+            "export-progress",
+            ExportProgress {
+                path,
+                bytes_written,
+                done,
+            },
+        );
+    };
+
+    file.write_all(prefix).await.map_err(write_error)?;
+    let mut written = prefix.len() as u64;
+    let mut last_report = Instant::now();
+    while let Some(chunk) = unless_cancelled(cancel, path, response.chunk())
+        .await?
+        .map_err(|err| HousecatError::Response(err.to_string()))?
+    {
+        file.write_all(&chunk).await.map_err(write_error)?;
+        written += chunk.len() as u64;
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            report(written, false);
+            last_report = Instant::now();
+        }
+    }
+    file.flush().await.map_err(write_error)?;
+    report(written, true);
+
+    Ok(written)
+}
+
+/// Runs a read-only query and streams its result to `path` in the chosen
+/// format, without holding the whole result in memory. Returns the number
+/// of bytes written, and reports it along the way in `export-progress`
+/// events keyed by path. `cancel_export` stops it with
+/// [`HousecatError::Cancelled`]. A partially written file is removed if the
+/// export fails or is cancelled midway.
+#[tauri::command]
+pub(crate) async fn export_query_result(
+    app: AppHandle,
+    input: ExportQueryInput,
+    registry: State<'_, ExportRegistry>,
+) -> Result<u64, HousecatError> {
+    let query = strip_format_clause(statement::require_read_only(&input.query)?);
+    let path = require_value(&input.path, "Export path")?;
+
+    let validation = check_export_path(path);
+    if let Some(problem) = validation.problem {
+        return Err(HousecatError::PathNotWritable(problem));
+    }
+
+    let excel_compatible = input.excel_compatible.unwrap_or(false);
+    if excel_compatible && input.format != ExportFormat::Csv {
+        return Err(HousecatError::invalid_input(
+            "Excel compatibility only applies to CSV exports",
+        ));
+    }
+
+    let query = format!("{query} FORMAT {}", input.format.clickhouse_format());
+    let cancel = registry.start(path)?;
+    let written = export_to_file(
+        &app,
+        &input.connection,
+        query,
+        path,
+        excel_compatible,
+        &cancel,
+    )
+    .await;
+    registry.finish(path);
+
+    written
+}
+
+async fn export_to_file(
+    app: &AppHandle,
+    connection: &ClickHouseConnectionInput,
+    query: String,
+    path: &str,
+    excel_compatible: bool,
+    cancel: &Notify,
+) -> Result<u64, HousecatError> {
+    let (params, prefix) = if excel_compatible {
+        (
+            vec![("output_format_csv_crlf_end_of_line", "1".to_string())],
+            UTF8_BOM,
+        )
+    } else {
+        (Vec::new(), &[][..])
+    };
+    let request = run_clickhouse_query_with_params(connection, query, &params);
+    let response = unless_cancelled(cancel, path, request).await??;
+
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .map_err(|err| HousecatError::Io(format!("Could not create {path}: {err}")))?;
+    let written = write_body(app, response, &mut file, path, prefix, cancel).await;
+    if written.is_err() {
+        // Waits out any write still in flight, so the handle is closed
+        // before the file is removed.
+        drop(file.into_std().await);
+        let _ = tokio::fs::remove_file(path).await;
+    }
+
+    written
+}
+
+/// Stops the running export to `path`, which then fails with
+/// [`HousecatError::Cancelled`] after removing the partial file. Returns
+/// whether such an export was running.
+#[tauri::command]
+pub(crate) fn cancel_export(path: String, registry: State<'_, ExportRegistry>) -> bool {
+    registry.cancel(path.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_format_clause;
+
+    #[test]
+    fn strips_format_after_non_ascii_literal() {
+        assert_eq!(
+            strip_format_clause("SELECT 'ı ﬀ' AS name FORMAT CSV"),
+            "SELECT 'ı ﬀ' AS name"
+        );
+    }
+
+    #[test]
+    fn ignores_format_inside_trailing_comment() {
+        assert_eq!(
+            strip_format_clause("SELECT 1 FORMAT CSV -- FORMAT note"),
+            "SELECT 1"
+        );
+    }
+
+    #[test]
+    fn keeps_format_inside_literal() {
+        let query = "SELECT 'FORMAT CSV'";
+        assert_eq!(strip_format_clause(query), query);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::error::HousecatError;
+use crate::{base_type, escape_identifier, fetch_rows, quote_string_literal, require_value};
+use crate::{ClickHouseConnectionInput, ClickHouseMetaColumn};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// A condition on one column of a table preview. Values are bound as
+/// `param_*` query parameters typed like the column, never spliced into
+/// the SQL, so ClickHouse parses them and rejects ones that do not fit.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub(crate) enum PreviewFilter {
+    Equals {
+        column: String,
+        value: String,
+    },
+    /// Case-insensitive substring match on the value's text form, so it
+    /// works on numbers and dates too.
+    Contains {
+        column: String,
+        value: String,
+    },
+    /// Inclusive on both ends; either end may be left open.
+    Range {
+        column: String,
+        min: Option<String>,
+        max: Option<String>,
+    },
+    IsNull {
+        column: String,
+    },
+}
+
+impl PreviewFilter {
+    fn column(&self) -> &str {
+        match self {
+            Self::Equals { column, .. }
+            | Self::Contains { column, .. }
+            | Self::Range { column, .. }
+            | Self::IsNull { column } => column,
+        }
+    }
+}
+
+/// Filters compiled for one table: a ` WHERE ...` clause, empty without
+/// filters, and the URL parameters its placeholders refer to.
+#[derive(Debug, Default)]
+pub(crate) struct CompiledFilters {
+    pub(crate) where_clause: String,
+    pub(crate) params: Vec<(String, String)>,
+}
+
+impl CompiledFilters {
+    pub(crate) fn url_params(&self) -> Vec<(&str, String)> {
+        self.params
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .collect()
+    }
+}
+
+/// Compiles `filters` against `schema.table`, looking up each column's
+/// type so placeholders can be typed to match it.
+pub(crate) async fn compile_filters(
+    connection: &ClickHouseConnectionInput,
+    schema: &str,
+    table: &str,
+    filters: &[PreviewFilter],
+) -> Result<CompiledFilters, HousecatError> {
+    if filters.is_empty() {
+        return Ok(CompiledFilters::default());
+    }
+
+    let types_query = format!(
+        "SELECT name, type FROM system.columns WHERE database = {} AND table = {} FORMAT JSON",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+    let types: BTreeMap<String, String> =
+        fetch_rows::<ClickHouseMetaColumn>(connection, types_query)
+            .await?
+            .into_iter()
+            .map(|column| (column.name, column.data_type))
+            .collect();
+
+    let mut conditions = Vec::with_capacity(filters.len());
+    let mut params = Vec::new();
+    for (index, filter) in filters.iter().enumerate() {
+        let column = require_value(filter.column(), "Filter column")?;
+        let data_type = types.get(column).ok_or_else(|| {
+            HousecatError::invalid_input(format!(
+                "Column `{column}` does not exist in {schema}.{table}"
+            ))
+        })?;
+        let data_type = base_type(data_type);
+        let identifier = format!("`{}`", escape_identifier(column));
+        let mut bind = |suffix: &str, value: &str| {
+            let name = format!("filter_{index}{suffix}");
+            params.push((format!("param_{name}"), value.to_string()));
+            name
+        };
+
+        let condition = match filter {
+            PreviewFilter::Equals { value, .. } => {
+                format!("{identifier} = {{{}:{data_type}}}", bind("", value))
+            }
+            PreviewFilter::Contains { value, .. } => format!(
+                "positionCaseInsensitiveUTF8(toString({identifier}), {{{}:String}}) > 0",
+                bind("", value)
+            ),
+            PreviewFilter::Range { min, max, .. } => {
+                let mut bounds = Vec::new();
+                if let Some(min) = min {
+                    bounds.push(format!(
+                        "{identifier} >= {{{}:{data_type}}}",
+                        bind("_min", min)
+                    ));
+                }
+                if let Some(max) = max {
+                    bounds.push(format!(
+                        "{identifier} <= {{{}:{data_type}}}",
+                        bind("_max", max)
+                    ));
+                }
+                if bounds.is_empty() {
+                    return Err(HousecatError::invalid_input(format!(
+                        "Range filter on `{column}` needs a minimum or a maximum"
+                    )));
+                }
+                bounds.join(" AND ")
+            }
+            PreviewFilter::IsNull { .. } => format!("isNull({identifier})"),
+        };
+        conditions.push(condition);
+    }
+
+    Ok(CompiledFilters {
+        where_clause: format!(" WHERE {}", conditions.join(" AND ")),
+        params,
+    })
+}
@@ -0,0 +1,80 @@
+use crate::error::HousecatError;
+use crate::{escape_identifier, qualified_table, require_value, value_literal};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GenerateInsertInput {
+    schema: String,
+    table: String,
+    columns: Vec<String>,
+    /// Optional column types, used to emit numeric values unquoted.
+    column_types: Option<BTreeMap<String, String>>,
+    /// Row objects as returned by previews, or positional arrays.
+    rows: Vec<Value>,
+}
+
+/// Builds an `INSERT ... VALUES` statement for the given rows without
+/// touching the server, so the user can review it before running it.
+#[tauri::command]
+pub(crate) fn generate_insert(input: GenerateInsertInput) -> Result<String, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+
+    if input.columns.is_empty() {
+        return Err(HousecatError::invalid_input(
+            "At least one column is required",
+        ));
+    }
+
+    if input.rows.is_empty() {
+        return Err(HousecatError::invalid_input("At least one row is required"));
+    }
+
+    let column_list = input
+        .columns
+        .iter()
+        .map(|column| format!("`{}`", escape_identifier(column)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let types = input.column_types.unwrap_or_default();
+
+    let tuples = input
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let values = input
+                .columns
+                .iter()
+                .enumerate()
+                .map(|(position, column)| {
+                    let value = match row {
+                        Value::Object(fields) => fields.get(column),
+                        Value::Array(values) => values.get(position),
+                        _ => None,
+                    }
+                    .ok_or_else(|| {
+                        HousecatError::invalid_input(format!(
+                            "Row {} has no value for column {column}",
+                            index + 1
+                        ))
+                    })?;
+
+                    Ok(value_literal(value, types.get(column).map(String::as_str)))
+                })
+                .collect::<Result<Vec<_>, HousecatError>>()?;
+
+            Ok(format!("({})", values.join(", ")))
+        })
+        .collect::<Result<Vec<_>, HousecatError>>()?;
+
+    Ok(format!(
+        "INSERT INTO {} ({column_list}) VALUES\n{}",
+        qualified_table(schema, table),
+        tuples.join(",\n")
+    ))
+}
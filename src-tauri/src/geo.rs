@@ -0,0 +1,72 @@
+use crate::{base_type, is_numeric_type, ColumnInfo};
+use serde::Serialize;
+
+/// Name tokens taken to mean latitude or longitude.
+const LATITUDE_NAMES: &[&str] = &["lat", "latitude"];
+const LONGITUDE_NAMES: &[&str] = &["lon", "lng", "long", "longitude"];
+
+/// Columns a result could be plotted from on a map.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub(crate) enum GeoColumns {
+    /// A `Point` column, holding `(longitude, latitude)` tuples.
+    Point { column: String },
+    /// Two numeric columns holding latitude and longitude separately.
+    LatLon { latitude: String, longitude: String },
+}
+
+/// The column's name with its latitude or longitude token replaced by a
+/// placeholder, so `pickup_lat` and `pickup_lon` share the stem `pickup_*`.
+fn coordinate_stem(name: &str, coordinate_names: &[&str]) -> Option<String> {
+    let lowercase = name.to_lowercase();
+    let tokens: Vec<&str> = lowercase.split('_').collect();
+    let position = tokens
+        .iter()
+        .position(|token| coordinate_names.contains(token))?;
+
+    let mut stem = tokens;
+    stem[position] = "*";
+    Some(stem.join("_"))
+}
+
+/// Guesses which result columns hold coordinates. Looked for, in order:
+///
+/// - Columns of type `Point`, after stripping `Nullable`/`LowCardinality`.
+/// - Numeric columns whose name has a `_`-separated token of `lat` or
+///   `latitude`, paired with a numeric column whose name is the same
+///   except for a `lon`, `lng`, `long` or `longitude` token in that place,
+///   matched case-insensitively: `lat`/`lon`, `pickup_latitude`/
+///   `pickup_longitude`, `LAT_dest`/`LNG_dest`.
+///
+/// A latitude column without a matching longitude is left out rather than
+/// paired with a guess.
+pub(crate) fn detect_geo_columns(columns: &[ColumnInfo]) -> Vec<GeoColumns> {
+    let mut detected: Vec<GeoColumns> = columns
+        .iter()
+        .filter(|column| base_type(&column.data_type) == "Point")
+        .map(|column| GeoColumns::Point {
+            column: column.name.clone(),
+        })
+        .collect();
+
+    let numeric = || {
+        columns
+            .iter()
+            .filter(|column| is_numeric_type(&column.data_type))
+    };
+    for latitude in numeric() {
+        let Some(stem) = coordinate_stem(&latitude.name, LATITUDE_NAMES) else {
+            continue;
+        };
+        let longitude = numeric()
+            .find(|column| coordinate_stem(&column.name, LONGITUDE_NAMES).as_ref() == Some(&stem));
+        if let Some(longitude) = longitude {
+            detected.push(GeoColumns::LatLon {
+                latitude: latitude.name.clone(),
+                longitude: longitude.name.clone(),
+            });
+        }
+    }
+
+    detected
+}
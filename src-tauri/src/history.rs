@@ -0,0 +1,387 @@
+use crate::error::HousecatError;
+use crate::{
+    escape_like_pattern, parse_preview_body, prepare_query, require_value,
+    run_clickhouse_query_with_params, settings, statement, ClickHouseConnectionInput, TablePreview,
+    QUOTE_DENORMALS_SETTING,
+};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+const HISTORY_FILE: &str = "query-history.sqlite3";
+
+const DEFAULT_HISTORY_LIMIT: u32 = 200;
+const MAX_HISTORY_LIMIT: u32 = 5_000;
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS query_history (
+    id INTEGER PRIMARY KEY,
+    query TEXT NOT NULL,
+    connection_id TEXT NOT NULL,
+    executed_at_unix_ms INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    rows_returned INTEGER,
+    error TEXT,
+    pinned INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS query_history_executed_at
+    ON query_history (executed_at_unix_ms);";
+
+const ENTRY_COLUMNS: &str =
+    "id, query, connection_id, executed_at_unix_ms, duration_ms, rows_returned, error, pinned";
+
+/// The history database, opened on first use in the app's data directory.
+#[derive(Default)]
+pub(crate) struct HistoryStore {
+    connection: Mutex<Option<Connection>>,
+}
+
+impl HistoryStore {
+    fn with_connection<T>(
+        &self,
+        app: &AppHandle,
+        f: impl FnOnce(&Connection) -> rusqlite::Result<T>,
+    ) -> Result<T, HousecatError> {
+        let mut connection = self
+            .connection
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        if connection.is_none() {
+            *connection = Some(open_database(app)?);
+        }
+
+        let connection = connection
+            .as_ref()
+            .expect("history database was just opened");
+        f(connection).map_err(history_error)
+    }
+}
+
+fn history_error(err: rusqlite::Error) -> HousecatError {
+    HousecatError::Io(format!("Query history failed: {err}"))
+}
+
+fn open_database(app: &AppHandle) -> Result<Connection, HousecatError> {
+    let directory = app
+        .path()
+        .app_data_dir()
+        .map_err(|err| HousecatError::Io(format!("Could not locate app data: {err}")))?;
+    std::fs::create_dir_all(&directory).map_err(|err| {
+        HousecatError::Io(format!("Could not create {}: {err}", directory.display()))
+    })?;
+
+    let connection = Connection::open(directory.join(HISTORY_FILE)).map_err(history_error)?;
+    connection.execute_batch(SCHEMA).map_err(history_error)?;
+    Ok(connection)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HistoryEntry {
+    id: i64,
+    query: String,
+    /// The connection's [`crate::clients::fingerprint`].
+    connection_id: String,
+    executed_at_unix_ms: i64,
+    duration_ms: i64,
+    /// `None` when the query failed.
+    rows_returned: Option<i64>,
+    error: Option<String>,
+    pinned: bool,
+}
+
+impl HistoryEntry {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            query: row.get(1)?,
+            connection_id: row.get(2)?,
+            executed_at_unix_ms: row.get(3)?,
+            duration_ms: row.get(4)?,
+            rows_returned: row.get(5)?,
+            error: row.get(6)?,
+            pinned: row.get(7)?,
+        })
+    }
+}
+
+/// How one execution ended, for [`record`].
+pub(crate) struct Execution<'a> {
+    pub(crate) query: &'a str,
+    pub(crate) connection_id: String,
+    pub(crate) duration: Duration,
+    pub(crate) outcome: Result<usize, &'a HousecatError>,
+}
+
+/// Appends an execution to the history. A history that cannot be written
+/// must not fail the query itself, so errors are dropped.
+pub(crate) fn record(app: &AppHandle, execution: Execution<'_>) {
+    let executed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or(0);
+    let (rows_returned, error) = match execution.outcome {
+        Ok(rows) => (Some(rows as i64), None),
+        Err(err) => (None, Some(err.to_string())),
+    };
+
+    let _ = app.state::<HistoryStore>().with_connection(app, |connection| {
+        connection.execute(
+            "INSERT INTO query_history (query, connection_id, executed_at_unix_ms, duration_ms, rows_returned, error) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                execution.query.trim(),
+                execution.connection_id,
+                executed_at,
+                execution.duration.as_millis() as i64,
+                rows_returned,
+                error,
+            ],
+        )
+    });
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HistoryListInput {
+    /// Only entries run on this connection, when given.
+    connection_id: Option<String>,
+    pinned_only: Option<bool>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HistorySearchInput {
+    text: String,
+    connection_id: Option<String>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PinQueryInput {
+    id: i64,
+    pinned: bool,
+}
+
+/// Runs `SELECT ... WHERE <condition>` over the history, newest first. The
+/// condition's parameters are `?1` (connection id, or NULL for any) and
+/// `?2` onwards.
+fn select_entries(
+    connection: &Connection,
+    condition: &str,
+    connection_id: Option<&str>,
+    extra: &[&dyn rusqlite::ToSql],
+    limit: Option<u32>,
+) -> rusqlite::Result<Vec<HistoryEntry>> {
+    let limit = limit
+        .unwrap_or(DEFAULT_HISTORY_LIMIT)
+        .clamp(1, MAX_HISTORY_LIMIT);
+    let mut statement = connection.prepare(&format!(
+        "SELECT {ENTRY_COLUMNS} FROM query_history WHERE (?1 IS NULL OR connection_id = ?1) AND {condition} ORDER BY executed_at_unix_ms DESC, id DESC LIMIT {limit}"
+    ))?;
+
+    let mut values: Vec<&dyn rusqlite::ToSql> = vec![&connection_id];
+    values.extend_from_slice(extra);
+    let entries = statement.query_map(values.as_slice(), HistoryEntry::from_row)?;
+    entries.collect()
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+/// Recent executions, newest first, optionally only pinned ones or those
+/// of one connection.
+#[tauri::command]
+pub(crate) fn list_query_history(
+    app: AppHandle,
+    store: State<'_, HistoryStore>,
+    input: HistoryListInput,
+) -> Result<Vec<HistoryEntry>, HousecatError> {
+    let pinned_only = input.pinned_only.unwrap_or(false);
+
+    store.with_connection(&app, |connection| {
+        select_entries(
+            connection,
+            "(?2 = 0 OR pinned = 1)",
+            non_empty(input.connection_id.as_deref()),
+            &[&pinned_only],
+            input.limit,
+        )
+    })
+}
+
+/// Executions whose SQL contains `text`, ignoring ASCII case, newest
+/// first.
+#[tauri::command]
+pub(crate) fn search_query_history(
+    app: AppHandle,
+    store: State<'_, HistoryStore>,
+    input: HistorySearchInput,
+) -> Result<Vec<HistoryEntry>, HousecatError> {
+    let text = require_value(&input.text, "Search text")?;
+    let pattern = format!("%{}%", escape_like_pattern(text));
+
+    store.with_connection(&app, |connection| {
+        select_entries(
+            connection,
+            "query LIKE ?2 ESCAPE '\\'",
+            non_empty(input.connection_id.as_deref()),
+            &[&pattern],
+            input.limit,
+        )
+    })
+}
+
+/// Removes one entry. Unknown ids are ignored.
+#[tauri::command]
+pub(crate) fn delete_history_entry(
+    app: AppHandle,
+    store: State<'_, HistoryStore>,
+    id: i64,
+) -> Result<(), HousecatError> {
+    store.with_connection(&app, |connection| {
+        connection.execute("DELETE FROM query_history WHERE id = ?1", params![id])
+    })?;
+    Ok(())
+}
+
+/// Pins or unpins an entry, so the UI can keep it as a snippet. Returns the
+/// updated entry.
+#[tauri::command]
+pub(crate) fn pin_query(
+    app: AppHandle,
+    store: State<'_, HistoryStore>,
+    input: PinQueryInput,
+) -> Result<HistoryEntry, HousecatError> {
+    store
+        .with_connection(&app, |connection| {
+            connection.execute(
+                "UPDATE query_history SET pinned = ?2 WHERE id = ?1",
+                params![input.id, input.pinned],
+            )?;
+            connection
+                .query_row(
+                    &format!("SELECT {ENTRY_COLUMNS} FROM query_history WHERE id = ?1"),
+                    params![input.id],
+                    HistoryEntry::from_row,
+                )
+                .optional()
+        })?
+        .ok_or_else(|| {
+            HousecatError::invalid_input(format!("History entry {} does not exist", input.id))
+        })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RunSavedQueryInput {
+    connection: ClickHouseConnectionInput,
+    /// A pinned history entry.
+    id: i64,
+    /// A value for every `{name:Type}` placeholder of the saved query, and
+    /// nothing else.
+    #[serde(default)]
+    params: BTreeMap<String, String>,
+    limit: Option<u32>,
+}
+
+fn names(names: impl IntoIterator<Item = impl AsRef<str>>) -> String {
+    names
+        .into_iter()
+        .map(|name| format!("`{}`", name.as_ref()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Runs a pinned query with `params` bound to its `{name:Type}`
+/// placeholders as server-side query parameters, like a small report.
+/// Fails before anything is sent when a placeholder has no value or a
+/// value has no placeholder.
+#[tauri::command]
+pub(crate) async fn run_saved_query(
+    app: AppHandle,
+    store: State<'_, HistoryStore>,
+    input: RunSavedQueryInput,
+) -> Result<TablePreview, HousecatError> {
+    let saved: Option<(String, bool)> = store.with_connection(&app, |connection| {
+        connection
+            .query_row(
+                "SELECT query, pinned FROM query_history WHERE id = ?1",
+                params![input.id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+    })?;
+    let query = match saved {
+        Some((query, true)) => query,
+        Some((_, false)) => {
+            return Err(HousecatError::invalid_input(format!(
+                "History entry {} is not pinned",
+                input.id
+            )))
+        }
+        None => {
+            return Err(HousecatError::invalid_input(format!(
+                "History entry {} does not exist",
+                input.id
+            )))
+        }
+    };
+
+    let query = match statement::split_statements(&query).as_slice() {
+        [query] => query.to_string(),
+        _ => {
+            return Err(HousecatError::invalid_input(
+                "Only a saved query of a single statement can be run with parameters",
+            ))
+        }
+    };
+    statement::require_allowed_on_connection(&query, &input.connection)?;
+
+    let placeholders = statement::query_placeholders(&query);
+    let missing: Vec<&String> = placeholders
+        .iter()
+        .filter(|name| !input.params.contains_key(*name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(HousecatError::invalid_input(format!(
+            "Missing values for query parameters {}",
+            names(missing)
+        )));
+    }
+    let extra: Vec<&String> = input
+        .params
+        .keys()
+        .filter(|name| !placeholders.contains(*name))
+        .collect();
+    if !extra.is_empty() {
+        return Err(HousecatError::invalid_input(format!(
+            "The saved query has no parameters {}",
+            names(extra)
+        )));
+    }
+
+    let query_parameters = settings::query_parameter_params(Some(&input.params))?;
+    let mut params: Vec<(&str, String)> = query_parameters
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.clone()))
+        .collect();
+    params.push((QUOTE_DENORMALS_SETTING, "1".to_string()));
+
+    let limit = input.limit.unwrap_or(500).clamp(1, 10_000);
+    let body = run_clickhouse_query_with_params(
+        &input.connection,
+        prepare_query(&query, Some(limit), "JSON")?,
+        &params,
+    )
+    .await?
+    .text()
+    .await
+    .map_err(|err| HousecatError::Response(err.to_string()))?;
+
+    Ok(parse_preview_body(&body))
+}
@@ -0,0 +1,307 @@
+use crate::error::HousecatError;
+use crate::schema::table_exists_in;
+use crate::{
+    clients, qualified_table, require_value, run_clickhouse_query_on, statement, tunnels,
+    ClickHouseConnectionInput,
+};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// Each `INSERT` carries at most this many rows or bytes of the file, so
+/// neither side has to hold all of it in memory.
+const IMPORT_BATCH_ROWS: u64 = 50_000;
+const IMPORT_BATCH_BYTES: usize = 8 * 1024 * 1024;
+
+/// Least time between two `import-progress` events of one import.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum ImportFormat {
+    Csv,
+    Tsv,
+    JsonEachRow,
+}
+
+impl ImportFormat {
+    fn clickhouse_format(self, header: bool) -> &'static str {
+        match (self, header) {
+            (Self::Csv, true) => "CSVWithNames",
+            (Self::Csv, false) => "CSV",
+            (Self::Tsv, true) => "TSVWithNames",
+            (Self::Tsv, false) => "TSV",
+            (Self::JsonEachRow, _) => "JSONEachRow",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportFileInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+    path: String,
+    format: ImportFormat,
+    /// Field separator of a CSV file; a comma when not given.
+    delimiter: Option<String>,
+    /// Whether the first row of a CSV or TSV file names the columns, which
+    /// are then matched to the table's by name. Defaults to true.
+    header: Option<bool>,
+    /// How a CSV or TSV file spells NULL, such as `\N` or an empty string.
+    null_string: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportResult {
+    rows_imported: u64,
+    bytes_read: u64,
+    batches: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportProgress<'a> {
+    path: &'a str,
+    rows_imported: u64,
+    bytes_read: u64,
+    done: bool,
+    /// Why the import stopped, on the final event of a failed import.
+    error: Option<String>,
+}
+
+/// Settings describing the file's dialect to ClickHouse's parser.
+fn format_params(input: &ImportFileInput) -> Result<Vec<(&'static str, String)>, HousecatError> {
+    let mut params = Vec::new();
+
+    if let Some(delimiter) = input.delimiter.as_deref() {
+        if input.format != ImportFormat::Csv {
+            return Err(HousecatError::invalid_input(
+                "A delimiter only applies to CSV files",
+            ));
+        }
+        let mut chars = delimiter.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii() && c != '"' && c != '\n' => {
+                params.push(("format_csv_delimiter", c.to_string()));
+            }
+            _ => {
+                return Err(HousecatError::invalid_input(
+                    "The delimiter must be a single ASCII character other than a quote or newline",
+                ))
+            }
+        }
+    }
+
+    if let Some(null_string) = input.null_string.as_deref() {
+        let setting = match input.format {
+            ImportFormat::Csv => "format_csv_null_representation",
+            ImportFormat::Tsv => "format_tsv_null_representation",
+            ImportFormat::JsonEachRow => {
+                return Err(HousecatError::invalid_input(
+                    "A null string only applies to CSV and TSV files",
+                ))
+            }
+        };
+        params.push((setting, null_string.to_string()));
+    }
+
+    Ok(params)
+}
+
+/// Sends the file to ClickHouse one batch of whole rows at a time. A batch
+/// of a file with a header row repeats the header, so every `INSERT`
+/// parses on its own.
+struct BatchInserter<'a> {
+    app: &'a AppHandle,
+    path: &'a str,
+    client: reqwest::Client,
+    connection: &'a ClickHouseConnectionInput,
+    params: &'a [(&'a str, String)],
+    /// The `INSERT ... FORMAT` line plus the header row, if any.
+    prefix: Vec<u8>,
+    batch: Vec<u8>,
+    batch_rows: u64,
+    rows_imported: u64,
+    bytes_read: u64,
+    batches: u64,
+    last_report: Instant,
+}
+
+impl BatchInserter<'_> {
+    async fn push_row(&mut self, row: &[u8]) -> Result<(), HousecatError> {
+        if row.iter().all(u8::is_ascii_whitespace) {
+            return Ok(());
+        }
+
+        self.batch.extend_from_slice(row);
+        if !row.ends_with(b"\n") {
+            self.batch.push(b'\n');
+        }
+        self.batch_rows += 1;
+
+        if self.batch_rows >= IMPORT_BATCH_ROWS || self.batch.len() >= IMPORT_BATCH_BYTES {
+            self.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), HousecatError> {
+        if self.batch_rows == 0 {
+            return Ok(());
+        }
+
+        let mut body = Vec::with_capacity(self.prefix.len() + self.batch.len());
+        body.extend_from_slice(&self.prefix);
+        body.append(&mut self.batch);
+
+        run_clickhouse_query_on(&self.client, self.connection, body, self.params)
+            .await
+            .map_err(|err| self.interrupted(err.to_string()))?;
+
+        self.rows_imported += self.batch_rows;
+        self.batch_rows = 0;
+        self.batches += 1;
+        if self.last_report.elapsed() >= PROGRESS_INTERVAL {
+            self.report(false, None);
+            self.last_report = Instant::now();
+        }
+        Ok(())
+    }
+
+    fn report(&self, done: bool, error: Option<String>) {
+        let _ = self.app.emit(
+            "import-progress",
+            ImportProgress {
+                path: self.path,
+                rows_imported: self.rows_imported,
+                bytes_read: self.bytes_read,
+                done,
+                error,
+            },
+        );
+    }
+
+    fn interrupted(&self, reason: String) -> HousecatError {
+        HousecatError::ImportInterrupted {
+            rows_imported: self.rows_imported,
+            reason,
+        }
+    }
+}
+
+/// Reads the next row of the file, returning false at its end. A CSV row
+/// may span several lines while a quoted field is open.
+async fn read_row(
+    reader: &mut BufReader<tokio::fs::File>,
+    format: ImportFormat,
+    row: &mut Vec<u8>,
+) -> std::io::Result<bool> {
+    row.clear();
+    let mut in_quotes = false;
+
+    loop {
+        let start = row.len();
+        if reader.read_until(b'\n', row).await? == 0 {
+            return Ok(!row.is_empty());
+        }
+        if format == ImportFormat::Csv {
+            let quotes = row[start..].iter().filter(|byte| **byte == b'"').count();
+            in_quotes ^= quotes % 2 == 1;
+        }
+        if !in_quotes {
+            return Ok(true);
+        }
+    }
+}
+
+/// Loads a local CSV, TSV or newline-delimited JSON file into an existing
+/// table, streaming it in batches of whole rows as `INSERT ... FORMAT`
+/// request bodies. Progress is reported in `import-progress` events keyed
+/// by path; the last one has `done` set and, if the import failed, the
+/// error. Batches already inserted stay in place when a later one fails,
+/// and the error says how many rows made it.
+#[tauri::command]
+pub(crate) async fn import_file(
+    app: AppHandle,
+    input: ImportFileInput,
+) -> Result<ImportResult, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+    let path = require_value(&input.path, "Import path")?;
+    let params = format_params(&input)?;
+    let header = input.header.unwrap_or(true) && input.format != ImportFormat::JsonEachRow;
+
+    let insert = format!(
+        "INSERT INTO {} FORMAT {}\n",
+        qualified_table(schema, table),
+        input.format.clickhouse_format(header)
+    );
+    statement::require_allowed_on_connection(&insert, &input.connection)?;
+    if !table_exists_in(&input.connection, schema, table).await? {
+        return Err(HousecatError::invalid_input(format!(
+            "Table {schema}.{table} does not exist"
+        )));
+    }
+
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|err| HousecatError::Io(format!("Could not open {path}: {err}")))?;
+    let mut reader = BufReader::new(file);
+    let read_error =
+        |err: std::io::Error| HousecatError::Io(format!("Could not read {path}: {err}"));
+
+    tunnels::ensure(&input.connection).await?;
+    let mut inserter = BatchInserter {
+        app: &app,
+        path,
+        client: clients::client_for(&input.connection)?,
+        connection: &input.connection,
+        params: &params,
+        prefix: insert.into_bytes(),
+        batch: Vec::new(),
+        batch_rows: 0,
+        rows_imported: 0,
+        bytes_read: 0,
+        batches: 0,
+        last_report: Instant::now(),
+    };
+
+    let mut row = Vec::new();
+    let result = async {
+        if header
+            && read_row(&mut reader, input.format, &mut row)
+                .await
+                .map_err(read_error)?
+        {
+            inserter.bytes_read += row.len() as u64;
+            inserter.prefix.extend_from_slice(&row);
+            if !row.ends_with(b"\n") {
+                inserter.prefix.push(b'\n');
+            }
+        }
+
+        while read_row(&mut reader, input.format, &mut row)
+            .await
+            .map_err(read_error)?
+        {
+            inserter.bytes_read += row.len() as u64;
+            inserter.push_row(&row).await?;
+        }
+        inserter.flush().await
+    }
+    .await;
+
+    inserter.report(true, result.as_ref().err().map(ToString::to_string));
+    result?;
+
+    Ok(ImportResult {
+        rows_imported: inserter.rows_imported,
+        bytes_read: inserter.bytes_read,
+        batches: inserter.batches,
+    })
+}
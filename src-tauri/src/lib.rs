@@ -1,21 +1,100 @@
+mod access;
+mod autocomplete;
+mod benchmark;
+mod clients;
+mod cluster;
+mod connections;
+mod copy;
+mod datetime;
+mod ddl;
+mod diagnostics;
+mod dictionaries;
+mod display;
+mod error;
+mod estimate;
+mod explain;
+mod export;
+mod filters;
+mod generate;
+mod geo;
+mod history;
+mod import;
+mod lint;
+mod monitoring;
+mod mutations;
+mod partitions;
+mod pipeline;
+mod plan;
+mod prefs;
+mod progress;
+mod query_cache;
+mod remote;
+mod render;
+mod schema;
+mod script;
+mod sessions;
+mod settings;
+mod statement;
+mod stats;
+mod stream;
+mod syntax;
+mod tail;
+mod tunnels;
+mod version;
+
+use error::HousecatError;
 use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::BTreeMap;
 use std::time::Duration;
 use std::time::Instant;
+use tauri::AppHandle;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct ClickHouseConnectionInput {
+pub(crate) struct ClickHouseConnectionInput {
     host: String,
     port: u16,
     username: String,
     password: String,
     database: Option<String>,
     secure: bool,
+    connect_timeout_secs: Option<u64>,
+    read_timeout_secs: Option<u64>,
+    session_id: Option<String>,
+    read_only: Option<bool>,
+    /// Shorthand for both timeouts, used where they are not set.
+    timeout_secs: Option<u64>,
+    /// Skips certificate verification entirely. Ignored when a private CA
+    /// is set, since it should then be verified against.
+    accept_invalid_certs: Option<bool>,
+    /// PEM certificates of a private CA to trust in addition to the
+    /// built-in roots.
+    ca_cert_pem: Option<String>,
+    /// Like `ca_cert_pem`, read from a PEM file. Both may be given.
+    ca_cert_path: Option<String>,
+    /// PEM files of a client certificate and its private key, for servers
+    /// that require mutual TLS. Set both or neither.
+    client_cert_path: Option<String>,
+    client_key_path: Option<String>,
+    /// Reaches the server through an SSH bastion when set.
+    ssh_tunnel: Option<tunnels::SshTunnelConfig>,
 }
 
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+/// The `readonly` level sent for read-only connections. Level 1 would also
+/// reject `SET` and per-query settings, so display settings such as
+/// `output_format_*` could not be applied; level 2 allows changing settings
+/// but still forbids writes and DDL. Writes are additionally rejected
+/// client-side, see [`statement::require_allowed_on_connection`].
+///
+/// This cannot relax a user whose server profile already has `readonly=1`;
+/// the server rejects the change for such users.
+const READ_ONLY_LEVEL: &str = "2";
+
 #[derive(Debug, Deserialize)]
 struct ClickHouseTableRow {
     database: String,
@@ -28,9 +107,16 @@ struct ClickHouseQueryResult {
     data: Vec<ClickHouseTableRow>,
 }
 
+#[derive(Debug, Deserialize)]
+struct ClickHouseRows<T> {
+    data: Vec<T>,
+}
+
 #[derive(Debug, Deserialize)]
 struct ClickHouseMetaColumn {
     name: String,
+    #[serde(rename = "type")]
+    data_type: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -39,6 +125,12 @@ struct ClickHousePreviewResult {
     data: Vec<Value>,
 }
 
+#[derive(Debug, Deserialize)]
+struct ClickHouseColumnarResult {
+    meta: Vec<ClickHouseMetaColumn>,
+    data: BTreeMap<String, Vec<Value>>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct TablePreviewInput {
@@ -48,14 +140,59 @@ struct TablePreviewInput {
     limit: Option<u32>,
     sort_column: Option<String>,
     sort_direction: Option<String>,
+    with_row_number: Option<bool>,
+    offset: Option<u32>,
+    /// Longest `String` cell, in characters, before it is cut short and
+    /// marked with [`TRUNCATION_MARKER`].
+    truncate_cells: Option<usize>,
+    /// Conditions every previewed row must meet, combined with `AND`.
+    filters: Option<Vec<filters::PreviewFilter>>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Appended to cells shortened by `truncate_cells`.
+const TRUNCATION_MARKER: &str = "…";
+
+/// Name of the virtual column added by `with_row_number`.
+const ROW_NUMBER_COLUMN: &str = "__row_number";
+
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct QueryInput {
     connection: ClickHouseConnectionInput,
     query: String,
     limit: Option<u32>,
+    columnar: Option<bool>,
+    report_progress: Option<bool>,
+    settings: Option<BTreeMap<String, String>>,
+    tags: Option<BTreeMap<String, String>>,
+    use_query_cache: Option<bool>,
+    stringify_numbers: Option<bool>,
+    locale: Option<String>,
+    coerce_display: Option<bool>,
+    /// Sent as ClickHouse's `query_id` so the query can be cancelled with
+    /// `cancel_query`. Generated when not given; either way it is announced
+    /// in a `query-started` event before the query runs.
+    query_id: Option<String>,
+    /// Values for `{name:Type}` placeholders in the query, sent as
+    /// `param_<name>` URL parameters. Also accepted as `params`.
+    #[serde(alias = "params")]
+    parameters: Option<BTreeMap<String, String>>,
+    /// Wall-clock budget for the query, enforced by the client and, via
+    /// `max_execution_time`, by the server.
+    deadline_ms: Option<u64>,
+    /// Adds a `geoColumns` hint naming coordinate columns in the result;
+    /// see [`geo::detect_geo_columns`]. The rows are unchanged.
+    detect_geo: Option<bool>,
+    /// Rows per chunk of `run_query_stream`; ignored by `run_query`.
+    batch_rows: Option<u32>,
+}
+
+impl QueryInput {
+    fn deadline(&self) -> Option<Duration> {
+        self.deadline_ms
+            .filter(|deadline| *deadline > 0)
+            .map(Duration::from_millis)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -83,11 +220,65 @@ struct SchemaTableEntry {
     row_count: Option<u64>,
 }
 
+/// A result column with its ClickHouse type, so the UI can align numbers or
+/// format dates without guessing from the values.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ColumnInfo {
+    name: String,
+    data_type: String,
+}
+
+impl From<ClickHouseMetaColumn> for ColumnInfo {
+    fn from(column: ClickHouseMetaColumn) -> Self {
+        Self {
+            name: column.name,
+            data_type: column.data_type,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct TablePreview {
-    columns: Vec<String>,
+    columns: Vec<ColumnInfo>,
     rows: Vec<Value>,
+    /// Rows in the whole table, for table previews of engines that track it.
+    total_rows: Option<u64>,
+    /// Rows matching a filtered table preview, across all pages.
+    matching_rows: Option<u64>,
+    /// Columns that could be drawn on a map, when `detectGeo` was asked for.
+    geo_columns: Option<Vec<geo::GeoColumns>>,
+}
+
+/// Column-oriented result: one array of values per column, keyed by name.
+/// `columns` keeps the select-list order, which the map alone would lose.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ColumnarPreview {
+    columns: Vec<ColumnInfo>,
+    data: BTreeMap<String, Vec<Value>>,
+    /// Columns that could be drawn on a map, when `detectGeo` was asked for.
+    geo_columns: Option<Vec<geo::GeoColumns>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum QueryOutput {
+    Rows(TablePreview),
+    Columns(ColumnarPreview),
+    /// One result per statement of a multi-statement script.
+    Script(Vec<script::StatementResult>),
+}
+
+impl QueryOutput {
+    fn row_count(&self) -> usize {
+        match self {
+            Self::Rows(preview) => preview.rows.len(),
+            Self::Columns(preview) => preview.data.values().next().map_or(0, Vec::len),
+            Self::Script(results) => results.iter().map(script::StatementResult::row_count).sum(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -99,38 +290,323 @@ struct ConnectionStatus {
     current_database: String,
 }
 
-fn escape_identifier(identifier: &str) -> String {
+pub(crate) fn escape_identifier(identifier: &str) -> String {
     identifier.replace('`', "``")
 }
 
-async fn run_clickhouse_query(
+/// Renders `value` as a single-quoted ClickHouse string literal.
+pub(crate) fn quote_string_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Strips `Nullable(...)` and `LowCardinality(...)` wrappers from a ClickHouse
+/// type name, leaving the type the values are actually stored as.
+pub(crate) fn base_type(data_type: &str) -> &str {
+    let mut data_type = data_type.trim();
+    for wrapper in ["Nullable(", "LowCardinality("] {
+        while let Some(inner) = data_type
+            .strip_prefix(wrapper)
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            data_type = inner.trim();
+        }
+    }
+
+    data_type
+}
+
+pub(crate) fn is_numeric_type(data_type: &str) -> bool {
+    let base = base_type(data_type);
+    ["Int", "UInt", "Float", "Decimal"]
+        .iter()
+        .any(|prefix| base.starts_with(prefix))
+}
+
+/// Renders a JSON value from a result row as a SQL literal. When the column
+/// type is known to be numeric, numbers ClickHouse sent as strings (64-bit
+/// integers and decimals) are emitted unquoted again.
+pub(crate) fn value_literal(value: &Value, data_type: Option<&str>) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Bool(flag) => flag.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::String(text)
+            if data_type.is_some_and(is_numeric_type) && text.parse::<f64>().is_ok() =>
+        {
+            text.clone()
+        }
+        Value::String(text) => quote_string_literal(text),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(|item| value_literal(item, None)).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(entries) => {
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|(key, item)| {
+                    format!(
+                        "{}: {}",
+                        quote_string_literal(key),
+                        value_literal(item, None)
+                    )
+                })
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+    }
+}
+
+pub(crate) fn qualified_table(schema: &str, table: &str) -> String {
+    format!(
+        "`{}`.`{}`",
+        escape_identifier(schema),
+        escape_identifier(table)
+    )
+}
+
+/// Trims `value` and rejects it when empty, naming the field in the error.
+pub(crate) fn require_value<'a>(value: &'a str, label: &str) -> Result<&'a str, HousecatError> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return Err(HousecatError::invalid_input(format!("{label} is required")));
+    }
+
+    Ok(trimmed)
+}
+
+/// Destructive commands require the caller to echo back the name of the
+/// object being changed, as typed by the user into a confirm dialog.
+pub(crate) fn require_confirmation(
+    confirmation: &str,
+    expected: &str,
+) -> Result<(), HousecatError> {
+    if confirmation.trim() != expected {
+        return Err(HousecatError::ConfirmationMismatch {
+            expected: expected.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn run_clickhouse_query(
+    input: &ClickHouseConnectionInput,
+    query: String,
+) -> Result<reqwest::Response, HousecatError> {
+    run_clickhouse_query_with_params(input, query, &[]).await
+}
+
+/// Like [`run_clickhouse_query`], additionally sending `params` as URL query
+/// parameters, which is how ClickHouse takes per-request settings over HTTP.
+pub(crate) async fn run_clickhouse_query_with_params(
     input: &ClickHouseConnectionInput,
     query: String,
-) -> Result<reqwest::Response, String> {
-    let host = input.host.trim();
-    if host.is_empty() {
-        return Err("Host is required".to_string());
+    params: &[(&str, String)],
+) -> Result<reqwest::Response, HousecatError> {
+    tunnels::ensure(input).await?;
+    let client = clients::client_for(input)?;
+    run_clickhouse_query_on(&client, input, query, params).await
+}
+
+impl ClickHouseConnectionInput {
+    pub(crate) fn connect_timeout(&self) -> u64 {
+        self.connect_timeout_secs
+            .or(self.timeout_secs)
+            .unwrap_or(DEFAULT_TIMEOUT_SECS)
+    }
+
+    pub(crate) fn read_timeout(&self) -> u64 {
+        self.read_timeout_secs
+            .or(self.timeout_secs)
+            .unwrap_or(DEFAULT_TIMEOUT_SECS)
+    }
+
+    /// The private CA, when one was given.
+    pub(crate) fn ca_cert_pem(&self) -> Option<&str> {
+        non_empty(self.ca_cert_pem.as_deref())
+    }
+
+    pub(crate) fn ca_cert_path(&self) -> Option<&str> {
+        non_empty(self.ca_cert_path.as_deref())
+    }
+
+    pub(crate) fn has_private_ca(&self) -> bool {
+        self.ca_cert_pem().is_some() || self.ca_cert_path().is_some()
+    }
+
+    pub(crate) fn client_cert_path(&self) -> Option<&str> {
+        non_empty(self.client_cert_path.as_deref())
+    }
+
+    pub(crate) fn client_key_path(&self) -> Option<&str> {
+        non_empty(self.client_key_path.as_deref())
+    }
+
+    pub(crate) fn accepts_invalid_certs(&self) -> bool {
+        !self.has_private_ca() && self.accept_invalid_certs.unwrap_or(false)
+    }
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+fn read_pem_file(path: &str, label: &str) -> Result<Vec<u8>, HousecatError> {
+    std::fs::read(path).map_err(|err| {
+        HousecatError::invalid_input(format!("Could not read {label} {path}: {err}"))
+    })
+}
+
+pub(crate) fn build_client(input: &ClickHouseConnectionInput) -> Result<Client, HousecatError> {
+    // The connect timeout bounds reaching the host; the read timeout bounds
+    // the whole request, so long-running queries need only raise the latter.
+    let mut builder = Client::builder()
+        .connect_timeout(Duration::from_secs(input.connect_timeout().max(1)))
+        .timeout(Duration::from_secs(input.read_timeout().max(1)))
+        .danger_accept_invalid_certs(input.accepts_invalid_certs());
+
+    // Through a tunnel the client connects to its local SOCKS5 listener;
+    // `socks5h` leaves name resolution to the bastion, and TLS still
+    // verifies the real host name.
+    if input.ssh_tunnel.is_some() {
+        let address = tunnels::local_address(input)
+            .ok_or_else(|| HousecatError::Connection("SSH tunnel is not open".to_string()))?;
+        let proxy = reqwest::Proxy::all(format!("socks5h://{address}"))
+            .map_err(|err| HousecatError::Client(err.to_string()))?;
+        builder = builder.proxy(proxy);
+    }
+
+    if input.has_private_ca() {
+        let mut bundle = input.ca_cert_pem().unwrap_or_default().as_bytes().to_vec();
+        if let Some(path) = input.ca_cert_path() {
+            bundle.push(b'\n');
+            bundle.extend(read_pem_file(path, "CA certificate")?);
+        }
+
+        let certificates = reqwest::Certificate::from_pem_bundle(&bundle).map_err(|err| {
+            HousecatError::invalid_input(format!("Invalid CA certificate: {err}"))
+        })?;
+        if certificates.is_empty() {
+            return Err(HousecatError::invalid_input(
+                "Invalid CA certificate: no PEM certificate found",
+            ));
+        }
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
     }
 
-    if input.username.trim().is_empty() {
-        return Err("Username is required".to_string());
+    let has_client_cert = match (input.client_cert_path(), input.client_key_path()) {
+        (Some(cert_path), Some(key_path)) => {
+            let mut pem = read_pem_file(cert_path, "client certificate")?;
+            pem.push(b'\n');
+            pem.extend(read_pem_file(key_path, "client key")?);
+            let identity = reqwest::Identity::from_pem(&pem).map_err(|err| {
+                HousecatError::invalid_input(format!("Invalid client certificate or key: {err}"))
+            })?;
+            builder = builder.identity(identity);
+            true
+        }
+        (None, None) => false,
+        _ => {
+            return Err(HousecatError::invalid_input(
+                "A client certificate needs both a certificate and a key file",
+            ))
+        }
+    };
+
+    builder.build().map_err(|err| {
+        if input.has_private_ca() || has_client_cert {
+            HousecatError::invalid_input(format!("TLS certificates were rejected: {err}"))
+        } else {
+            HousecatError::Client(err.to_string())
+        }
+    })
+}
+
+/// Whether a failed send may be retried on a fresh connection. A failed
+/// connect never reached ClickHouse, so any query may be retried. A pooled
+/// keep-alive connection can also close after the whole request went out,
+/// so that is only retried for reads: replaying a write such as an INSERT
+/// batch could apply it twice.
+fn is_retryable_send_error(err: &reqwest::Error, read_only: bool) -> bool {
+    if err.is_connect() {
+        return true;
+    }
+    if !read_only {
+        return false;
+    }
+
+    let mut source = std::error::Error::source(err);
+    while let Some(cause) = source {
+        if cause
+            .to_string()
+            .contains("connection closed before message completed")
+        {
+            return true;
+        }
+        source = cause.source();
     }
 
+    false
+}
+
+/// Sends `query` through an existing `client`, reusing its open connections.
+/// The query may be raw bytes, for inserts whose data follows the statement.
+pub(crate) async fn run_clickhouse_query_on(
+    client: &Client,
+    input: &ClickHouseConnectionInput,
+    query: impl Into<reqwest::Body>,
+    params: &[(&str, String)],
+) -> Result<reqwest::Response, HousecatError> {
+    let host = require_value(&input.host, "Host")?;
+    require_value(&input.username, "Username")?;
+
     let scheme = if input.secure { "https" } else { "http" };
     let endpoint = format!("{scheme}://{host}:{}/", input.port);
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(10))
-        .build()
-        .map_err(|err| format!("Could not initialize ClickHouse client: {err}"))?;
-
-    let response = client
+    let mut request = client
         .post(endpoint)
-        .basic_auth(input.username.trim(), Some(&input.password))
+        .basic_auth(input.username.trim(), Some(&input.password));
+
+    if let Some(session_id) = input.session_id.as_deref().filter(|id| !id.is_empty()) {
+        request = request.query(&[("session_id", session_id)]);
+    }
+
+    if input.read_only.unwrap_or(false) {
+        request = request.query(&[("readonly", READ_ONLY_LEVEL)]);
+    }
+
+    let request = request
+        .query(params)
         .body(query)
-        .send()
-        .await
-        .map_err(|err| format!("Could not connect to ClickHouse: {err}"))?;
+        .build()
+        .map_err(|err| HousecatError::Client(err.to_string()))?;
+    let retry = request.try_clone();
+    // Only the leading keyword is inspected, so a prefix of an insert's
+    // data is plenty.
+    let read_only = request
+        .body()
+        .and_then(reqwest::Body::as_bytes)
+        .is_some_and(|body| {
+            let head = String::from_utf8_lossy(&body[..body.len().min(4096)]);
+            statement::classify_statement(&head).is_read_only()
+        });
+
+    let response = match client.execute(request).await {
+        Ok(response) => response,
+        // After a network change the first attempt can fail on a dead socket.
+        // The pool may hold more of them, so drop it and retry on a fresh one.
+        Err(err) if is_retryable_send_error(&err, read_only) => match retry {
+            Some(retry) => {
+                clients::evict(input);
+                clients::client_for(input)?.execute(retry).await
+            }
+            None => Err(err),
+        }
+        .map_err(|err| HousecatError::Connection(err.to_string()))?,
+        Err(err) => return Err(HousecatError::Connection(err.to_string())),
+    };
 
     if !response.status().is_success() {
         let status = response.status();
@@ -139,32 +615,82 @@ async fn run_clickhouse_query(
             .await
             .unwrap_or_else(|_| "Unable to read error body".to_string());
 
-        return Err(format!("ClickHouse returned {status}: {body}"));
+        return Err(HousecatError::from_response(status.as_u16(), body));
     }
 
     Ok(response)
 }
 
+/// Runs a `FORMAT JSON` query and deserializes its `data` rows. 64-bit
+/// integers are requested unquoted so they land in plain `u64`/`i64` fields.
+pub(crate) async fn fetch_rows<T: DeserializeOwned>(
+    input: &ClickHouseConnectionInput,
+    query: String,
+) -> Result<Vec<T>, HousecatError> {
+    fetch_rows_with_params(input, query, &[]).await
+}
+
+pub(crate) async fn fetch_rows_with_params<T: DeserializeOwned>(
+    input: &ClickHouseConnectionInput,
+    query: String,
+    params: &[(&str, String)],
+) -> Result<Vec<T>, HousecatError> {
+    let mut params = params.to_vec();
+    params.push(("output_format_json_quote_64bit_integers", "0".to_string()));
+
+    let response = run_clickhouse_query_with_params(input, query, &params).await?;
+
+    let result: ClickHouseRows<T> = response
+        .json()
+        .await
+        .map_err(|err| HousecatError::Parse(err.to_string()))?;
+
+    Ok(result.data)
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseCountRow {
+    count: u64,
+}
+
+/// Runs a `SELECT count() AS count ... FORMAT JSON` query.
+pub(crate) async fn fetch_count(
+    input: &ClickHouseConnectionInput,
+    query: String,
+) -> Result<u64, HousecatError> {
+    let rows: Vec<ClickHouseCountRow> = fetch_rows(input, query).await?;
+    Ok(rows.first().map_or(0, |row| row.count))
+}
+
 #[tauri::command]
 async fn fetch_schema_tables(
     input: ClickHouseConnectionInput,
-) -> Result<Vec<SchemaTables>, String> {
+) -> Result<Vec<SchemaTables>, HousecatError> {
     let query = match input.database.as_deref() {
         Some(database) if !database.trim().is_empty() => {
-            let escaped_database = database.trim().replace('\'', "''");
             format!(
-                "SELECT database, name, total_rows FROM system.tables WHERE database = '{escaped_database}' ORDER BY name FORMAT JSON"
+                "SELECT database, name, total_rows FROM system.tables WHERE database = {} ORDER BY name FORMAT JSON",
+                quote_string_literal(database.trim())
             )
         }
         _ => "SELECT database, name, total_rows FROM system.tables WHERE database NOT IN ('INFORMATION_SCHEMA', 'information_schema', 'system') ORDER BY database, name FORMAT JSON".to_string(),
     };
 
-    let response = run_clickhouse_query(&input, query).await?;
+    fetch_grouped_tables(&input, query).await
+}
+
+/// Runs a `database, name, total_rows` query against `system.tables` and
+/// groups the rows by database.
+async fn fetch_grouped_tables(
+    input: &ClickHouseConnectionInput,
+    query: String,
+) -> Result<Vec<SchemaTables>, HousecatError> {
+    let response = run_clickhouse_query(input, query).await?;
 
     let result: ClickHouseQueryResult = response
         .json()
         .await
-        .map_err(|err| format!("Could not parse ClickHouse response: {err}"))?;
+        .map_err(|err| HousecatError::Parse(err.to_string()))?;
 
     let mut grouped: BTreeMap<String, Vec<SchemaTableEntry>> = BTreeMap::new();
 
@@ -186,102 +712,470 @@ async fn fetch_schema_tables(
     Ok(schemas)
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CommentSearchInput {
+    connection: ClickHouseConnectionInput,
+    pattern: String,
+}
+
+/// Escapes `%`, `_` and `\` so `text` matches literally inside a `LIKE`
+/// pattern.
+pub(crate) fn escape_like_pattern(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Finds tables whose comment contains `pattern`, case-insensitively.
 #[tauri::command]
-async fn fetch_table_preview(input: TablePreviewInput) -> Result<TablePreview, String> {
-    let schema = input.schema.trim();
-    if schema.is_empty() {
-        return Err("Schema is required".to_string());
-    }
+async fn search_tables_by_comment(
+    input: CommentSearchInput,
+) -> Result<Vec<SchemaTables>, HousecatError> {
+    let pattern = require_value(&input.pattern, "Search pattern")?;
 
-    let table = input.table.trim();
-    if table.is_empty() {
-        return Err("Table is required".to_string());
-    }
+    let query = format!(
+        "SELECT database, name, total_rows FROM system.tables WHERE comment ILIKE {} AND database NOT IN ('INFORMATION_SCHEMA', 'information_schema', 'system') ORDER BY database, name FORMAT JSON",
+        quote_string_literal(&format!("%{}%", escape_like_pattern(pattern)))
+    );
 
-    let limit = input.limit.unwrap_or(200).clamp(1, 1000);
-    let order_clause = match input.sort_column {
-        Some(column) if !column.trim().is_empty() => {
-            let direction = match input.sort_direction.as_deref() {
+    fetch_grouped_tables(&input.connection, query).await
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseTableInfoRow {
+    total_rows: Option<u64>,
+    sorting_key: String,
+}
+
+/// The `ORDER BY` of a table preview: the expression and the URL parameters
+/// it refers to.
+struct PreviewOrder {
+    expression: String,
+    params: Vec<(&'static str, String)>,
+}
+
+/// Orders a table preview by the requested sort column, else by
+/// `fallback_key` when one applies. The column is bound as an `Identifier`
+/// query parameter rather than spliced into the SQL, so ClickHouse quotes
+/// it.
+fn preview_order_by(
+    sort_column: Option<&str>,
+    sort_direction: Option<&str>,
+    fallback_key: Option<&str>,
+) -> Option<PreviewOrder> {
+    match sort_column.map(str::trim) {
+        Some(column) if !column.is_empty() => {
+            let direction = match sort_direction {
                 Some("desc") | Some("DESC") => "DESC",
                 _ => "ASC",
             };
 
-            format!(
-                " ORDER BY `{}` {}",
-                escape_identifier(column.trim()),
-                direction
-            )
+            Some(PreviewOrder {
+                expression: format!("{{sort_column:Identifier}} {direction}"),
+                params: vec![("param_sort_column", column.to_string())],
+            })
+        }
+        _ => fallback_key.map(|key| PreviewOrder {
+            expression: key.to_string(),
+            params: Vec::new(),
+        }),
+    }
+}
+
+async fn fetch_table_info(
+    connection: &ClickHouseConnectionInput,
+    schema: &str,
+    table: &str,
+) -> Result<Option<ClickHouseTableInfoRow>, HousecatError> {
+    let query = format!(
+        "SELECT total_rows, sorting_key FROM system.tables WHERE database = {} AND name = {} FORMAT JSON",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+
+    Ok(fetch_rows(connection, query).await?.into_iter().next())
+}
+
+#[tauri::command]
+async fn fetch_table_preview(input: TablePreviewInput) -> Result<TablePreview, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+
+    let limit = input.limit.unwrap_or(200).clamp(1, 1000);
+
+    let info = fetch_table_info(&input.connection, schema, table).await?;
+    let filters = filters::compile_filters(
+        &input.connection,
+        schema,
+        table,
+        input.filters.as_deref().unwrap_or_default(),
+    )
+    .await?;
+    let mut params = filters.url_params();
+
+    // Pages only line up if every page is read in the same order. Without a
+    // sort column, paginated previews fall back to the table's sorting key;
+    // tables without one (non-MergeTree engines) return rows in whatever
+    // order the engine reads them, which may differ between pages.
+    // Truncated previews are ordered the same way, so a cut-short cell can
+    // be fetched again by its position with `fetch_preview_cell`.
+    let truncate_cells = input.truncate_cells.filter(|length| *length > 0);
+    let sorting_key = info
+        .as_ref()
+        .map(|info| info.sorting_key.trim())
+        .filter(|key| (input.offset.is_some() || truncate_cells.is_some()) && !key.is_empty());
+
+    let order_by = preview_order_by(
+        input.sort_column.as_deref(),
+        input.sort_direction.as_deref(),
+        sorting_key,
+    )
+    .map(|order| {
+        params.extend(order.params);
+        order.expression
+    });
+
+    let order_clause = order_by
+        .as_ref()
+        .map(|order_by| format!(" ORDER BY {order_by}"))
+        .unwrap_or_default();
+
+    // Row numbers follow the preview's sort so they stay attached to the same
+    // rows when the UI re-sorts locally; unsorted previews number rows in
+    // read order instead.
+    let row_number = match (input.with_row_number.unwrap_or(false), &order_by) {
+        (false, _) => String::new(),
+        (true, Some(order_by)) => {
+            format!("row_number() OVER (ORDER BY {order_by}) AS {ROW_NUMBER_COLUMN}, ")
         }
+        (true, None) => format!("rowNumberInAllBlocks() + 1 AS {ROW_NUMBER_COLUMN}, "),
+    };
+
+    // Offsets past the end simply yield no rows.
+    let offset_clause = match input.offset {
+        Some(offset) if offset > 0 => format!(" OFFSET {offset}"),
         _ => String::new(),
     };
 
-    let query = format!(
-        "SELECT * FROM `{}`.`{}`{} LIMIT {} FORMAT JSON",
-        escape_identifier(schema),
-        escape_identifier(table),
+    let mut query = format!(
+        "SELECT {}* FROM {}{}{} LIMIT {}{}",
+        row_number,
+        qualified_table(schema, table),
+        filters.where_clause,
         order_clause,
-        limit
+        limit,
+        offset_clause
     );
 
-    let response = run_clickhouse_query(&input.connection, query).await?;
+    // Truncation wraps the page in an outer select, so sorting still sees
+    // the full values.
+    if let Some(length) = truncate_cells {
+        // `SELECT *` leaves out materialized and alias columns, so they are
+        // left out here as well.
+        let columns_query = format!(
+            "SELECT name, type FROM system.columns WHERE database = {} AND table = {} AND default_kind NOT IN ('MATERIALIZED', 'ALIAS', 'EPHEMERAL') ORDER BY position FORMAT JSON",
+            quote_string_literal(schema),
+            quote_string_literal(table)
+        );
+        let columns: Vec<ClickHouseMetaColumn> =
+            fetch_rows(&input.connection, columns_query).await?;
+        let mut select_list: Vec<String> = columns
+            .iter()
+            .map(|column| {
+                let name = escape_identifier(&column.name);
+                if base_type(&column.data_type) == "String" {
+                    format!(
+                        "if(lengthUTF8(`{name}`) > {length}, concat(substringUTF8(`{name}`, 1, {length}), {}), `{name}`) AS `{name}`",
+                        quote_string_literal(TRUNCATION_MARKER)
+                    )
+                } else {
+                    format!("`{name}`")
+                }
+            })
+            .collect();
+        if !row_number.is_empty() {
+            select_list.insert(0, ROW_NUMBER_COLUMN.to_string());
+        }
+
+        query = format!("SELECT {} FROM ({query})", select_list.join(", "));
+    }
+    query.push_str(" FORMAT JSON");
+
+    let response = run_clickhouse_query_with_params(&input.connection, query, &params).await?;
 
     let preview_result: ClickHousePreviewResult = response
         .json()
         .await
-        .map_err(|err| format!("Could not parse ClickHouse response: {err}"))?;
+        .map_err(|err| HousecatError::Parse(err.to_string()))?;
 
     let columns = preview_result
         .meta
         .into_iter()
-        .map(|col| col.name)
+        .map(ColumnInfo::from)
         .collect();
 
+    // Exact, but it only reads the filtered columns.
+    let matching_rows = if filters.where_clause.is_empty() {
+        None
+    } else {
+        let count_query = format!(
+            "SELECT count() AS count FROM {}{} FORMAT JSON",
+            qualified_table(schema, table),
+            filters.where_clause
+        );
+        fetch_rows_with_params::<ClickHouseCountRow>(&input.connection, count_query, &params)
+            .await?
+            .first()
+            .map(|row| row.count)
+    };
+
     Ok(TablePreview {
         columns,
         rows: preview_result.data,
+        total_rows: info.and_then(|info| info.total_rows),
+        matching_rows,
+        geo_columns: None,
     })
 }
 
+/// Runs a `FORMAT JSON` query selecting `column` and returns its value in
+/// the first row, or `None` when no row matched. `params` carries the
+/// query's own parameters, if it has any.
+async fn fetch_single_cell(
+    connection: &ClickHouseConnectionInput,
+    query: String,
+    column: &str,
+    params: &[(&str, String)],
+) -> Result<Option<Value>, HousecatError> {
+    let mut params = params.to_vec();
+    params.push((QUOTE_DENORMALS_SETTING, "1".to_string()));
+    let rows: Vec<BTreeMap<String, Value>> =
+        fetch_rows_with_params(connection, query, &params).await?;
+
+    Ok(rows
+        .into_iter()
+        .next()
+        .and_then(|mut row| row.remove(column)))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PreviewCellInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+    column: String,
+    /// Zero-based position of the row in the preview's order, counting
+    /// rows on earlier pages.
+    row_index: u32,
+    sort_column: Option<String>,
+    sort_direction: Option<String>,
+    /// The preview's filters, which positions are counted under.
+    filters: Option<Vec<filters::PreviewFilter>>,
+}
+
+/// The full value of one cell of a truncated preview, found by its row
+/// position under the same ordering `fetch_table_preview` used. Positions
+/// only stay stable while the table is not being written to.
 #[tauri::command]
-async fn run_query(input: QueryInput) -> Result<TablePreview, String> {
-    let raw_query = input.query.trim().trim_end_matches(';').trim();
-    if raw_query.is_empty() {
-        return Err("Query is required".to_string());
+async fn fetch_preview_cell(input: PreviewCellInput) -> Result<Value, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+    let column = require_value(&input.column, "Column")?;
+
+    let info = fetch_table_info(&input.connection, schema, table).await?;
+    let sorting_key = info
+        .as_ref()
+        .map(|info| info.sorting_key.trim())
+        .filter(|key| !key.is_empty());
+    let order = preview_order_by(
+        input.sort_column.as_deref(),
+        input.sort_direction.as_deref(),
+        sorting_key,
+    );
+    let order_clause = order
+        .as_ref()
+        .map(|order| format!(" ORDER BY {}", order.expression))
+        .unwrap_or_default();
+
+    let filters = filters::compile_filters(
+        &input.connection,
+        schema,
+        table,
+        input.filters.as_deref().unwrap_or_default(),
+    )
+    .await?;
+    let mut params = filters.url_params();
+    params.extend(order.into_iter().flat_map(|order| order.params));
+
+    let query = format!(
+        "SELECT `{}` FROM {}{}{order_clause} LIMIT 1 OFFSET {} FORMAT JSON",
+        escape_identifier(column),
+        qualified_table(schema, table),
+        filters.where_clause,
+        input.row_index
+    );
+    fetch_single_cell(&input.connection, query, column, &params)
+        .await?
+        .ok_or_else(|| {
+            HousecatError::invalid_input(format!("Row {} does not exist", input.row_index))
+        })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyRangeInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+    key_column: String,
+    start: Value,
+    end: Value,
+    limit: Option<u32>,
+}
+
+fn key_bound_literal(bound: &Value, data_type: &str, label: &str) -> Result<String, HousecatError> {
+    match bound {
+        Value::String(_) | Value::Number(_) | Value::Bool(_) => {
+            Ok(value_literal(bound, Some(data_type)))
+        }
+        _ => Err(HousecatError::invalid_input(format!(
+            "{label} must be a string or a number"
+        ))),
     }
+}
+
+/// Pages through a table by a range of its sorting key. Unlike `OFFSET`,
+/// which reads and discards every skipped row, a key range lets the
+/// primary index seek straight to the first matching granule.
+#[tauri::command]
+async fn fetch_rows_by_key_range(input: KeyRangeInput) -> Result<TablePreview, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+    let key_column = require_value(&input.key_column, "Key column")?;
+    let limit = input.limit.unwrap_or(200).clamp(1, 1000);
+
+    let data_type = schema::fetch_column_type(&input.connection, schema, table, key_column)
+        .await?
+        .ok_or_else(|| {
+            HousecatError::invalid_input(format!(
+                "Column `{key_column}` does not exist in {schema}.{table}"
+            ))
+        })?;
+
+    let query = format!(
+        "SELECT * FROM {} WHERE `{key}` BETWEEN {} AND {} ORDER BY `{key}` LIMIT {limit} FORMAT JSON",
+        qualified_table(schema, table),
+        key_bound_literal(&input.start, &data_type, "Range start")?,
+        key_bound_literal(&input.end, &data_type, "Range end")?,
+        key = escape_identifier(key_column)
+    );
+
+    let body = run_clickhouse_query_with_params(
+        &input.connection,
+        query,
+        &[(QUOTE_DENORMALS_SETTING, "1".to_string())],
+    )
+    .await?
+    .text()
+    .await
+    .map_err(|err| HousecatError::Response(err.to_string()))?;
+
+    Ok(parse_preview_body(&body))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CellInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+    key_column: String,
+    key_value: Value,
+    column: String,
+}
+
+/// The full value of `column` in the row whose `key_column` equals
+/// `key_value`, for expanding a truncated cell. The key value is quoted for
+/// the key column's type. With a non-unique key the first match is used.
+#[tauri::command]
+async fn fetch_cell(input: CellInput) -> Result<Value, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+    let key_column = require_value(&input.key_column, "Key column")?;
+    let column = require_value(&input.column, "Column")?;
+
+    let data_type = schema::fetch_column_type(&input.connection, schema, table, key_column)
+        .await?
+        .ok_or_else(|| {
+            HousecatError::invalid_input(format!(
+                "Column `{key_column}` does not exist in {schema}.{table}"
+            ))
+        })?;
+    let key_value = key_bound_literal(&input.key_value, &data_type, "Key value")?;
+
+    let query = format!(
+        "SELECT `{}` FROM {} WHERE `{}` = {key_value} LIMIT 1 FORMAT JSON",
+        escape_identifier(column),
+        qualified_table(schema, table),
+        escape_identifier(key_column)
+    );
+
+    fetch_single_cell(&input.connection, query, column, &[])
+        .await?
+        .ok_or_else(|| {
+            HousecatError::invalid_input(format!("No row has `{key_column}` = {key_value}"))
+        })
+}
 
-    let mut query = raw_query.to_string();
+/// Normalizes a user-entered query the way the query editor expects: drops a
+/// trailing `;`, caps bare `SELECT`s at `limit` rows, if given, and appends
+/// `format` unless the user chose one.
+pub(crate) fn prepare_query(
+    raw_query: &str,
+    limit: Option<u32>,
+    format: &str,
+) -> Result<String, HousecatError> {
+    let mut query = statement::clean_statement(raw_query)?.to_string();
     let uppercase_query = query.to_uppercase();
-    let limit = input.limit.unwrap_or(500).clamp(1, 10_000);
 
-    if uppercase_query.starts_with("SELECT ") && !uppercase_query.contains(" LIMIT ") {
-        query.push_str(&format!(" LIMIT {limit}"));
+    if let Some(limit) = limit {
+        if uppercase_query.starts_with("SELECT ") && !uppercase_query.contains(" LIMIT ") {
+            query.push_str(&format!(" LIMIT {limit}"));
+        }
     }
 
     if !uppercase_query.contains("FORMAT ") {
-        query.push_str(" FORMAT JSON");
+        query.push_str(&format!(" FORMAT {format}"));
     }
 
-    let response = run_clickhouse_query(&input.connection, query).await?;
-
-    let body = response
-        .text()
-        .await
-        .map_err(|err| format!("Could not read ClickHouse response: {err}"))?;
+    Ok(query)
+}
 
-    if let Ok(preview_result) = serde_json::from_str::<ClickHousePreviewResult>(&body) {
+/// Parses a `FORMAT JSON` body, falling back to a single `result` cell for
+/// statements that return plain text or nothing at all.
+pub(crate) fn parse_preview_body(body: &str) -> TablePreview {
+    if let Ok(preview_result) = serde_json::from_str::<ClickHousePreviewResult>(body) {
         let columns = preview_result
             .meta
             .into_iter()
-            .map(|col| col.name)
+            .map(ColumnInfo::from)
             .collect();
-        return Ok(TablePreview {
+        return TablePreview {
             columns,
             rows: preview_result.data,
-        });
+            total_rows: None,
+            matching_rows: None,
+            geo_columns: None,
+        };
     }
 
-    Ok(TablePreview {
-        columns: vec!["result".to_string()],
+    TablePreview {
+        columns: vec![ColumnInfo {
+            name: "result".to_string(),
+            data_type: "String".to_string(),
+        }],
         rows: vec![json!({
             "result": if body.trim().is_empty() {
                 "Query executed successfully"
@@ -289,13 +1183,278 @@ async fn run_query(input: QueryInput) -> Result<TablePreview, String> {
                 body.trim()
             }
         })],
-    })
+        total_rows: None,
+        matching_rows: None,
+        geo_columns: None,
+    }
+}
+
+// Bare `nan`/`inf` are not valid JSON and would make the body unparsable,
+// so have ClickHouse emit them as quoted strings instead.
+const QUOTE_DENORMALS_SETTING: &str = "output_format_json_quote_denormals";
+
+/// Runs a user query through the same pipeline as `run_query` and returns
+/// its rows, for commands that render or post-process results.
+pub(crate) async fn fetch_query_preview(
+    connection: &ClickHouseConnectionInput,
+    raw_query: &str,
+    limit: u32,
+) -> Result<TablePreview, HousecatError> {
+    let query = prepare_query(raw_query, Some(limit), "JSON")?;
+    let body = run_clickhouse_query_with_params(
+        connection,
+        query,
+        &[(QUOTE_DENORMALS_SETTING, "1".to_string())],
+    )
+    .await?
+    .text()
+    .await
+    .map_err(|err| HousecatError::Response(err.to_string()))?;
+
+    Ok(parse_preview_body(&body))
+}
+
+/// URL parameters shared by every way of running a [`QueryInput`]: its
+/// settings, query parameters, tags and query cache switch.
+fn query_input_params<'a>(
+    input: &'a QueryInput,
+    query_parameters: &'a [(String, String)],
+) -> Result<Vec<(&'a str, String)>, HousecatError> {
+    let mut params = settings::settings_params(input.settings.as_ref())?;
+    params.extend(
+        query_parameters
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone())),
+    );
+    if let Some(log_comment) = settings::tags_log_comment(input.tags.as_ref())? {
+        if params.iter().any(|(name, _)| *name == log_comment.0) {
+            return Err(HousecatError::invalid_input(
+                "Set either tags or a log_comment setting, not both",
+            ));
+        }
+        params.push(log_comment);
+    }
+    if let Some(deadline) = input.deadline() {
+        if params.iter().any(|(name, _)| *name == "max_execution_time") {
+            return Err(HousecatError::invalid_input(
+                "Set either deadlineMs or a max_execution_time setting, not both",
+            ));
+        }
+        // Whole seconds, rounded up so the client gives up first and keeps
+        // whatever it has received.
+        params.push((
+            "max_execution_time",
+            deadline.as_millis().div_ceil(1000).to_string(),
+        ));
+    }
+    params.push((QUOTE_DENORMALS_SETTING, "1".to_string()));
+    if let Some(use_query_cache) = input.use_query_cache {
+        params.push(("use_query_cache", u8::from(use_query_cache).to_string()));
+    }
+
+    Ok(params)
+}
+
+/// Runs a query from the editor and records it in the query history,
+/// whether it succeeded or not. A script of several `;`-separated
+/// statements is run one statement at a time, see [`script::run_script`].
+#[tauri::command]
+async fn run_query(app: AppHandle, mut input: QueryInput) -> Result<QueryOutput, HousecatError> {
+    let mut statements: Vec<String> = statement::split_statements(&input.query)
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    if statements.len() > 1 {
+        return Ok(QueryOutput::Script(
+            script::run_script(&app, input, statements).await,
+        ));
+    }
+
+    // The lone statement, without the `;` and comments around it, so the
+    // LIMIT and FORMAT appended later are not swallowed by a comment.
+    input.query = statements
+        .pop()
+        .ok_or_else(|| HousecatError::invalid_input("Query is required"))?;
+    execute_recorded(&app, input)
+        .await
+        .map(|(output, _)| output)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReadOnlyOverrideInput {
+    #[serde(flatten)]
+    query: QueryInput,
+    /// Must repeat the connection's host, so lifting the guard always takes
+    /// a deliberate step.
+    confirmation: String,
+}
+
+/// Runs a query on a read-only connection as if it were not read-only, for
+/// the deliberate write that [`HousecatError::ReadOnly`] blocked. The
+/// override covers this one call; the connection stays read-only.
+#[tauri::command]
+async fn run_query_overriding_read_only(
+    app: AppHandle,
+    input: ReadOnlyOverrideInput,
+) -> Result<QueryOutput, HousecatError> {
+    let mut query = input.query;
+    require_confirmation(&input.confirmation, query.connection.host.trim())?;
+    query.connection.read_only = Some(false);
+
+    run_query(app, query).await
+}
+
+/// Executes one statement and records it in the query history.
+async fn execute_recorded(
+    app: &AppHandle,
+    input: QueryInput,
+) -> Result<(QueryOutput, benchmark::QuerySummary), HousecatError> {
+    let query = input.query.clone();
+    let connection_id = clients::fingerprint(&input.connection);
+    let started = Instant::now();
+
+    let result = execute_query(app.clone(), input).await;
+    history::record(
+        app,
+        history::Execution {
+            query: &query,
+            connection_id,
+            duration: started.elapsed(),
+            outcome: result.as_ref().map(|(output, _)| output.row_count()),
+        },
+    );
+
+    result
+}
+
+/// Executes one statement, returning its result along with the server's
+/// summary of what it read and wrote.
+async fn execute_query(
+    app: AppHandle,
+    input: QueryInput,
+) -> Result<(QueryOutput, benchmark::QuerySummary), HousecatError> {
+    let limit = input.limit.unwrap_or(500).clamp(1, 10_000);
+    let columnar = input.columnar.unwrap_or(false);
+
+    // JSONColumns plus a `meta` header, so column order survives.
+    let format = if columnar {
+        "JSONColumnsWithMetadata"
+    } else {
+        "JSON"
+    };
+    statement::require_allowed_on_connection(&input.query, &input.connection)?;
+
+    let stringify_numbers = input.stringify_numbers.unwrap_or(false);
+    if input.locale.is_some() && !stringify_numbers {
+        return Err(HousecatError::invalid_input(
+            "A locale only applies together with stringifyNumbers",
+        ));
+    }
+    let number_format = display::NumberFormat::for_locale(input.locale.as_deref())?;
+
+    let query_parameters = settings::query_parameter_params(input.parameters.as_ref())?;
+    let mut params = query_input_params(&input, &query_parameters)?;
+    if stringify_numbers {
+        // Every number becomes a string anyway, so let 64-bit integers
+        // arrive as numbers and be grouped like the rest.
+        params.push(("output_format_json_quote_64bit_integers", "0".to_string()));
+    }
+
+    let query = if input.coerce_display.unwrap_or(false) {
+        let coerced =
+            display::coerce_display_query(&input.connection, &input.query, &params).await?;
+        prepare_query(&coerced, Some(limit), format)?
+    } else {
+        prepare_query(&input.query, Some(limit), format)?
+    };
+
+    let query_id = match input.query_id.as_deref() {
+        Some(query_id) => require_value(query_id, "Query id")?.to_string(),
+        None => uuid::Uuid::new_v4().to_string(),
+    };
+    params.push(("query_id", query_id.clone()));
+    progress::emit_query_started(&app, &query_id);
+
+    let poller = input
+        .report_progress
+        .unwrap_or(false)
+        .then(|| progress::spawn_progress_poller(app, input.connection.clone(), query_id));
+
+    let request = async {
+        let response = run_clickhouse_query_with_params(&input.connection, query, &params).await?;
+        let summary = benchmark::parse_summary_header(&response);
+        let body = response
+            .text()
+            .await
+            .map_err(|err| HousecatError::Response(err.to_string()))?;
+        Ok((body, summary))
+    };
+    // Without streaming there is no partial result to return, so a missed
+    // deadline is an error whichever side noticed it first.
+    let result = match (input.deadline(), input.deadline_ms) {
+        (Some(deadline), Some(deadline_ms)) => tokio::time::timeout(deadline, request)
+            .await
+            .unwrap_or(Err(HousecatError::DeadlineExceeded(deadline_ms)))
+            .map_err(|err| {
+                if err.is_timeout_exceeded() {
+                    HousecatError::DeadlineExceeded(deadline_ms)
+                } else {
+                    err
+                }
+            }),
+        _ => request.await,
+    };
+
+    if let Some(poller) = poller {
+        poller.abort();
+    }
+
+    let (body, summary) = result?;
+    let detect_geo = input.detect_geo.unwrap_or(false);
+
+    if columnar {
+        if let Ok(mut columnar_result) = serde_json::from_str::<ClickHouseColumnarResult>(&body) {
+            if stringify_numbers {
+                columnar_result
+                    .data
+                    .values_mut()
+                    .flatten()
+                    .for_each(|value| number_format.stringify(value));
+            }
+            let columns: Vec<ColumnInfo> = columnar_result
+                .meta
+                .into_iter()
+                .map(ColumnInfo::from)
+                .collect();
+            let geo_columns = detect_geo.then(|| geo::detect_geo_columns(&columns));
+            let output = QueryOutput::Columns(ColumnarPreview {
+                columns,
+                data: columnar_result.data,
+                geo_columns,
+            });
+            return Ok((output, summary));
+        }
+    }
+
+    let mut preview = parse_preview_body(&body);
+    if stringify_numbers {
+        preview
+            .rows
+            .iter_mut()
+            .for_each(|row| number_format.stringify(row));
+    }
+    if detect_geo {
+        preview.geo_columns = Some(geo::detect_geo_columns(&preview.columns));
+    }
+
+    Ok((QueryOutput::Rows(preview), summary))
 }
 
 #[tauri::command]
 async fn fetch_connection_status(
     input: ClickHouseConnectionInput,
-) -> Result<ConnectionStatus, String> {
+) -> Result<ConnectionStatus, HousecatError> {
     let started = Instant::now();
     let response = run_clickhouse_query(
         &input,
@@ -307,13 +1466,13 @@ async fn fetch_connection_status(
     let result: ClickHouseStatusResult = response
         .json()
         .await
-        .map_err(|err| format!("Could not parse ClickHouse response: {err}"))?;
+        .map_err(|err| HousecatError::Parse(err.to_string()))?;
 
     let row = result
         .data
         .into_iter()
         .next()
-        .ok_or_else(|| "Could not read ClickHouse status".to_string())?;
+        .ok_or_else(|| HousecatError::Response("no status row returned".to_string()))?;
 
     Ok(ConnectionStatus {
         connected: true,
@@ -327,12 +1486,161 @@ async fn fetch_connection_status(
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_dialog::init())
+        .manage(sessions::SessionRegistry::default())
+        .manage(tail::TailRegistry::default())
+        .manage(history::HistoryStore::default())
+        .manage(autocomplete::AutocompleteCache::default())
+        .manage(export::ExportRegistry::default())
         .invoke_handler(tauri::generate_handler![
             fetch_schema_tables,
+            search_tables_by_comment,
             fetch_table_preview,
+            fetch_preview_cell,
+            fetch_cell,
+            fetch_rows_by_key_range,
             run_query,
-            fetch_connection_status
+            run_query_overriding_read_only,
+            stream::run_query_stream,
+            fetch_connection_status,
+            clients::reset_connection_pool,
+            clients::reset_all_connection_pools,
+            clients::connection_fingerprint,
+            partitions::detach_partition,
+            partitions::attach_partition,
+            partitions::fetch_partition_counts,
+            partitions::fetch_partition_time_ranges,
+            partitions::fetch_part_size_distribution,
+            prefs::save_table_view_prefs,
+            prefs::load_table_view_prefs,
+            partitions::move_partition,
+            schema::snapshot_schema,
+            schema::detect_schema_drift,
+            schema::table_exists,
+            schema::fetch_columns_detailed,
+            schema::fetch_table_details,
+            schema::fetch_column_defaults,
+            schema::detect_time_column,
+            schema::fetch_tables_by_engine,
+            schema::compare_schemas_across,
+            monitoring::fetch_async_insert_status,
+            monitoring::fetch_disks,
+            monitoring::fetch_memory_breakdown,
+            monitoring::fetch_top_queries,
+            monitoring::fetch_running_queries,
+            monitoring::kill_query_by_id,
+            monitoring::cancel_query,
+            monitoring::fetch_query_settings,
+            monitoring::fetch_queries_by_tag,
+            monitoring::fetch_slow_queries,
+            monitoring::fetch_recent_query_log,
+            monitoring::fetch_profile_events,
+            monitoring::fetch_server_logs,
+            monitoring::find_unused_columns,
+            monitoring::fetch_replication_queue,
+            sessions::open_session,
+            sessions::fetch_sessions,
+            sessions::close_session,
+            sessions::fetch_temporary_tables,
+            sessions::open_connection,
+            sessions::close_connection,
+            connections::list_connections,
+            connections::save_connection,
+            connections::delete_connection,
+            connections::test_connection,
+            history::list_query_history,
+            history::search_query_history,
+            history::delete_history_entry,
+            history::pin_query,
+            history::run_saved_query,
+            diagnostics::diagnose_connection,
+            diagnostics::server_now,
+            diagnostics::run_query_debug,
+            render::export_query_markdown,
+            render::export_query_html,
+            export::export_query_result,
+            export::cancel_export,
+            import::import_file,
+            export::validate_export_path,
+            estimate::estimate_memory,
+            generate::generate_insert,
+            mutations::estimate_mutation_impact,
+            mutations::supports_lightweight_delete,
+            mutations::lightweight_delete,
+            ddl::rename_table,
+            ddl::add_column,
+            stats::approx_distinct,
+            stats::fetch_null_rates,
+            benchmark::compare_with_settings,
+            benchmark::benchmark_throughput,
+            benchmark::warm_cache,
+            access::fetch_table_access,
+            access::fetch_settings_profiles,
+            tail::tail_table,
+            tail::stop_tail,
+            query_cache::query_cache_eligible,
+            version::require_min_version,
+            explain::explain_error,
+            plan::explain_query,
+            autocomplete::fetch_autocomplete_metadata,
+            autocomplete::start_autocomplete_refresh,
+            autocomplete::stop_autocomplete_refresh,
+            cluster::fetch_cluster_topology,
+            cluster::fetch_replica_status,
+            cluster::fetch_distributed_tables,
+            datetime::normalize_datetime,
+            remote::preview_remote,
+            copy::copy_table_data,
+            lint::lint_query,
+            pipeline::explain_pipeline_graph,
+            syntax::canonical_sql,
+            dictionaries::fetch_dictionary_sources,
+            stats::rolling_aggregate,
+            mutations::mutation_health,
+            diagnostics::export_repro_bundle
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The body ClickHouse sends for `SELECT 1/0 AS inf, 0/0 AS nan` with
+    /// denormals quoted.
+    const QUOTED_DENORMALS_BODY: &str = r#"{
+        "meta": [{"name": "inf", "type": "Float64"}, {"name": "nan", "type": "Float64"}],
+        "data": [{"inf": "inf", "nan": "nan"}],
+        "rows": 1
+    }"#;
+
+    #[test]
+    fn parses_quoted_denormal_floats() {
+        let preview = parse_preview_body(QUOTED_DENORMALS_BODY);
+
+        assert_eq!(preview.columns.len(), 2);
+        assert_eq!(preview.columns[0].data_type, "Float64");
+        assert_eq!(preview.rows, vec![json!({"inf": "inf", "nan": "nan"})]);
+    }
+
+    #[test]
+    fn run_query_quotes_denormals() {
+        let input: QueryInput = serde_json::from_value(json!({
+            "connection": {
+                "host": "localhost",
+                "port": 8123,
+                "username": "default",
+                "password": "",
+                "secure": false
+            },
+            "query": "SELECT 1/0 AS inf"
+        }))
+        .expect("query input deserializes");
+
+        let params = query_input_params(&input, &[]).expect("params build");
+        assert!(params
+            .iter()
+            .any(|(name, value)| *name == "output_format_json_quote_denormals" && value == "1"));
+    }
+}
@@ -0,0 +1,131 @@
+use crate::statement::{non_deterministic_functions, tokens};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LintQueryInput {
+    query: String,
+    /// Sorting key columns of the table the query filters, when the UI knows
+    /// them. Enables the sort-key check.
+    sort_key: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum LintSeverity {
+    Info,
+    Warning,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LintWarning {
+    code: &'static str,
+    severity: LintSeverity,
+    message: String,
+}
+
+impl LintWarning {
+    fn new(code: &'static str, severity: LintSeverity, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+/// Keywords that end a `WHERE` clause.
+const WHERE_TERMINATORS: &[&str] = &[
+    "GROUP", "ORDER", "LIMIT", "HAVING", "SETTINGS", "FORMAT", "UNION", "WINDOW", "QUALIFY",
+];
+
+/// `*` right after `SELECT`, `DISTINCT` or a comma selects all columns; in
+/// any other position it is multiplication or `count(*)`.
+fn selects_star(tokens: &[String]) -> bool {
+    tokens
+        .windows(2)
+        .any(|pair| pair[1] == "*" && matches!(pair[0].as_str(), "SELECT" | "DISTINCT" | ","))
+}
+
+fn where_clause(tokens: &[String]) -> Option<&[String]> {
+    let start = tokens.iter().position(|token| token == "WHERE")? + 1;
+    let end = tokens[start..]
+        .iter()
+        .position(|token| WHERE_TERMINATORS.contains(&token.as_str()))
+        .map_or(tokens.len(), |offset| start + offset);
+    Some(&tokens[start..end])
+}
+
+fn lint(query: &str, sort_key: Option<&[String]>) -> Vec<LintWarning> {
+    let tokens = tokens(query);
+    let mut warnings = Vec::new();
+    let is_select = matches!(
+        tokens.first().map(String::as_str),
+        Some("SELECT" | "WITH" | "(")
+    );
+
+    if is_select && selects_star(&tokens) {
+        warnings.push(LintWarning::new(
+            "selectStar",
+            LintSeverity::Warning,
+            "SELECT * reads every column; on wide tables naming only the needed columns is much cheaper",
+        ));
+    }
+
+    if is_select && !tokens.iter().any(|token| token == "LIMIT") {
+        warnings.push(LintWarning::new(
+            "missingLimit",
+            LintSeverity::Info,
+            "SELECT without LIMIT may return more rows than needed",
+        ));
+    }
+
+    if tokens.iter().any(|token| token == "FINAL") {
+        warnings.push(LintWarning::new(
+            "final",
+            LintSeverity::Warning,
+            "FINAL merges rows at query time and can be much slower on large tables",
+        ));
+    }
+
+    let functions = non_deterministic_functions(query);
+    if !functions.is_empty() {
+        warnings.push(LintWarning::new(
+            "nonDeterministic",
+            LintSeverity::Info,
+            format!(
+                "{} make the result change between runs, so it cannot be served from the query cache",
+                functions.join(", ")
+            ),
+        ));
+    }
+
+    if let (Some(sort_key), Some(condition)) = (sort_key, where_clause(&tokens)) {
+        let first_key = sort_key.first().map(|column| column.trim());
+        let uses_key = first_key.is_some_and(|key| {
+            condition
+                .iter()
+                .any(|token| token.eq_ignore_ascii_case(key))
+        });
+
+        if let Some(key) = first_key.filter(|_| !uses_key) {
+            warnings.push(LintWarning::new(
+                "whereMissesSortKey",
+                LintSeverity::Warning,
+                format!(
+                    "The WHERE clause does not filter on `{key}`, the first sorting key column, so the primary index cannot skip data"
+                ),
+            ));
+        }
+    }
+
+    warnings
+}
+
+/// Advisory checks on a query before it runs. Works on the text alone; the
+/// sort-key check runs only when `sort_key` is supplied.
+#[tauri::command]
+pub(crate) fn lint_query(input: LintQueryInput) -> Vec<LintWarning> {
+    lint(&input.query, input.sort_key.as_deref())
+}
@@ -0,0 +1,704 @@
+use crate::error::{HousecatError, UNKNOWN_TABLE};
+use crate::{datetime, schema};
+use crate::{
+    fetch_count, fetch_rows, quote_string_literal, require_value, ClickHouseConnectionInput,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct AsyncInsertEntry {
+    database: String,
+    table: String,
+    query: String,
+    format: String,
+    total_bytes: u64,
+    first_update: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AsyncInsertStatus {
+    pending_bytes: u64,
+    entries: Vec<AsyncInsertEntry>,
+}
+
+#[tauri::command]
+pub(crate) async fn fetch_async_insert_status(
+    input: ClickHouseConnectionInput,
+) -> Result<AsyncInsertStatus, HousecatError> {
+    let result = fetch_rows::<AsyncInsertEntry>(
+        &input,
+        "SELECT database, table, query, format, total_bytes, toString(first_update) AS first_update FROM system.asynchronous_inserts ORDER BY first_update FORMAT JSON"
+            .to_string(),
+    )
+    .await;
+
+    // Servers without async insert support lack the table entirely.
+    let entries = match result {
+        Ok(entries) => entries,
+        Err(err) if err.clickhouse_code() == Some(UNKNOWN_TABLE) => Vec::new(),
+        Err(err) => return Err(err),
+    };
+
+    Ok(AsyncInsertStatus {
+        pending_bytes: entries.iter().map(|entry| entry.total_bytes).sum(),
+        entries,
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct DiskInfo {
+    name: String,
+    path: String,
+    free_space: u64,
+    total_space: u64,
+    #[serde(rename(deserialize = "type"))]
+    disk_type: String,
+}
+
+/// Lists storage disks with the fullest first, to show the headroom left
+/// before a large insert or export.
+#[tauri::command]
+pub(crate) async fn fetch_disks(
+    input: ClickHouseConnectionInput,
+) -> Result<Vec<DiskInfo>, HousecatError> {
+    fetch_rows(
+        &input,
+        "SELECT name, path, free_space, total_space, type FROM system.disks ORDER BY free_space ASC, name FORMAT JSON"
+            .to_string(),
+    )
+    .await
+}
+
+/// Server memory split by consumer, in bytes. `tracked` is the total the
+/// server's memory tracker accounts for; the other consumers are parts of
+/// it, except `resident` and `osTotal`, which come from the OS.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct MemoryBreakdown {
+    tracked: i64,
+    queries: i64,
+    merges: i64,
+    caches: i64,
+    dictionaries: i64,
+    resident: i64,
+    os_total: i64,
+}
+
+/// Cache sizes reported in `system.asynchronous_metrics`. Older servers
+/// lack some of them, which then simply count as zero.
+const CACHE_METRICS: &[&str] = &[
+    "MarkCacheBytes",
+    "UncompressedCacheBytes",
+    "PrimaryIndexCacheBytes",
+    "QueryCacheBytes",
+    "PageCacheBytes",
+];
+
+#[tauri::command]
+pub(crate) async fn fetch_memory_breakdown(
+    input: ClickHouseConnectionInput,
+) -> Result<MemoryBreakdown, HousecatError> {
+    let cache_metrics: Vec<String> = CACHE_METRICS
+        .iter()
+        .map(|metric| quote_string_literal(metric))
+        .collect();
+
+    let query = format!(
+        "SELECT \
+            toInt64(ifNull((SELECT value FROM system.metrics WHERE metric = 'MemoryTracking'), 0)) AS tracked, \
+            toInt64(ifNull((SELECT sum(memory_usage) FROM system.processes), 0)) AS queries, \
+            toInt64(ifNull((SELECT sum(memory_usage) FROM system.merges), 0)) AS merges, \
+            toInt64(ifNull((SELECT sum(value) FROM system.asynchronous_metrics WHERE metric IN ({})), 0)) AS caches, \
+            toInt64(ifNull((SELECT sum(bytes_allocated) FROM system.dictionaries), 0)) AS dictionaries, \
+            toInt64(ifNull((SELECT value FROM system.asynchronous_metrics WHERE metric = 'MemoryResident'), 0)) AS resident, \
+            toInt64(ifNull((SELECT value FROM system.asynchronous_metrics WHERE metric = 'OSMemoryTotal'), 0)) AS os_total \
+        FORMAT JSON",
+        cache_metrics.join(", ")
+    );
+
+    fetch_rows::<MemoryBreakdown>(&input, query)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| HousecatError::Response("no memory row returned".to_string()))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TopQueriesInput {
+    connection: ClickHouseConnectionInput,
+    by: String,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct TopQuery {
+    query_id: String,
+    query: String,
+    user: String,
+    event_time: String,
+    metric: u64,
+}
+
+/// Lists the most expensive finished queries in `system.query_log`, ranked
+/// by `readBytes`, `memoryUsage` or `duration`.
+#[tauri::command]
+pub(crate) async fn fetch_top_queries(
+    input: TopQueriesInput,
+) -> Result<Vec<TopQuery>, HousecatError> {
+    let metric = match input.by.trim() {
+        "readBytes" | "read_bytes" => "read_bytes",
+        "memoryUsage" | "memory_usage" => "memory_usage",
+        "duration" | "query_duration_ms" => "query_duration_ms",
+        other => {
+            return Err(HousecatError::invalid_input(format!(
+                "Cannot rank queries by `{other}`; use readBytes, memoryUsage or duration"
+            )))
+        }
+    };
+    let limit = input.limit.unwrap_or(20).clamp(1, 100);
+
+    let query = format!(
+        "SELECT query_id, query, user, toString(event_time) AS event_time, {metric} AS metric FROM system.query_log WHERE type = 'QueryFinish' ORDER BY {metric} DESC LIMIT {limit} FORMAT JSON"
+    );
+
+    fetch_rows(&input.connection, query)
+        .await
+        .map_err(|err| err.missing_table_as_unavailable("system.query_log"))
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct RunningQuery {
+    query_id: String,
+    user: String,
+    query: String,
+    elapsed: f64,
+    memory_usage: i64,
+    read_rows: u64,
+    read_bytes: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct KillQueryInput {
+    connection: ClickHouseConnectionInput,
+    query_id: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct KilledQuery {
+    kill_status: String,
+    query_id: String,
+    user: String,
+    query: String,
+}
+
+/// Lists queries currently executing on the server, excluding this listing
+/// query itself.
+#[tauri::command]
+pub(crate) async fn fetch_running_queries(
+    input: ClickHouseConnectionInput,
+) -> Result<Vec<RunningQuery>, HousecatError> {
+    fetch_rows(
+        &input,
+        "SELECT query_id, user, query, elapsed, memory_usage, read_rows, read_bytes FROM system.processes WHERE query_id != queryID() ORDER BY elapsed DESC FORMAT JSON"
+            .to_string(),
+    )
+    .await
+}
+
+/// Issues `KILL QUERY` for one query id. `SYNC` waits until the query has
+/// actually stopped; otherwise the kill is only requested. Fails with
+/// [`HousecatError::QueryNotRunning`] when nothing matched.
+pub(crate) async fn kill_query(
+    connection: &ClickHouseConnectionInput,
+    query_id: &str,
+    sync: bool,
+) -> Result<Vec<KilledQuery>, HousecatError> {
+    let query_id = require_value(query_id, "Query id")?;
+    let mode = if sync { "SYNC" } else { "ASYNC" };
+
+    let killed: Vec<KilledQuery> = fetch_rows(
+        connection,
+        format!(
+            "KILL QUERY WHERE query_id = {} {mode} FORMAT JSON",
+            quote_string_literal(query_id)
+        ),
+    )
+    .await?;
+
+    if killed.is_empty() {
+        return Err(HousecatError::QueryNotRunning(query_id.to_string()));
+    }
+
+    Ok(killed)
+}
+
+#[tauri::command]
+pub(crate) async fn kill_query_by_id(
+    input: KillQueryInput,
+) -> Result<Vec<KilledQuery>, HousecatError> {
+    kill_query(&input.connection, &input.query_id, false).await
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CancelQueryInput {
+    connection: ClickHouseConnectionInput,
+    query_id: String,
+    timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CancelResult {
+    query_id: String,
+    /// Whether the query left `system.processes` before the timeout.
+    stopped: bool,
+    /// Time from the kill request until the query was gone, or until the
+    /// timeout when it was not.
+    latency_ms: u64,
+}
+
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const DEFAULT_CANCEL_TIMEOUT_MS: u64 = 10_000;
+const MAX_CANCEL_TIMEOUT_MS: u64 = 60_000;
+
+/// Cancels a running query with `KILL QUERY ... SYNC`, then confirms in
+/// `system.processes` that it is gone, reporting how long the server took
+/// to honor the cancel. Fails with [`HousecatError::QueryNotRunning`] when
+/// the query had already finished.
+#[tauri::command]
+pub(crate) async fn cancel_query(input: CancelQueryInput) -> Result<CancelResult, HousecatError> {
+    let query_id = require_value(&input.query_id, "Query id")?;
+    let timeout = Duration::from_millis(
+        input
+            .timeout_ms
+            .unwrap_or(DEFAULT_CANCEL_TIMEOUT_MS)
+            .clamp(1, MAX_CANCEL_TIMEOUT_MS),
+    );
+
+    // The query's own session is busy running it, so act from outside.
+    let mut connection = input.connection.clone();
+    connection.session_id = None;
+
+    let started = Instant::now();
+    kill_query(&connection, query_id, true).await?;
+
+    let still_running = format!(
+        "SELECT count() AS count FROM system.processes WHERE query_id = {} FORMAT JSON",
+        quote_string_literal(query_id)
+    );
+    let stopped = loop {
+        if fetch_count(&connection, still_running.clone()).await? == 0 {
+            break true;
+        }
+        if started.elapsed() >= timeout {
+            break false;
+        }
+        tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+    };
+
+    Ok(CancelResult {
+        query_id: query_id.to_string(),
+        stopped,
+        latency_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QuerySettingsInput {
+    connection: ClickHouseConnectionInput,
+    query_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseSettingsRow {
+    settings: BTreeMap<String, String>,
+}
+
+/// Settings a running query runs with, as `system.processes` reports them:
+/// every setting changed from the server default, whether by the query,
+/// its session or the user's profile.
+#[tauri::command]
+pub(crate) async fn fetch_query_settings(
+    input: QuerySettingsInput,
+) -> Result<BTreeMap<String, String>, HousecatError> {
+    let query_id = require_value(&input.query_id, "Query id")?;
+
+    // The query's own session is busy running it, so look from outside.
+    let mut connection = input.connection.clone();
+    connection.session_id = None;
+
+    let rows: Vec<ClickHouseSettingsRow> = fetch_rows(
+        &connection,
+        format!(
+            "SELECT Settings AS settings FROM system.processes WHERE query_id = {} FORMAT JSON",
+            quote_string_literal(query_id)
+        ),
+    )
+    .await?;
+
+    rows.into_iter()
+        .next()
+        .map(|row| row.settings)
+        .ok_or_else(|| HousecatError::QueryNotRunning(query_id.to_string()))
+}
+
+/// Columns selected for [`QueryLogEntry`], shared by the query log commands.
+const QUERY_LOG_COLUMNS: &str = "query_id, toString(type) AS type, toString(event_time) AS event_time, query_duration_ms, read_rows, read_bytes, result_rows, memory_usage, user, query, exception";
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct QueryLogEntry {
+    query_id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    event_time: String,
+    query_duration_ms: u64,
+    read_rows: u64,
+    read_bytes: u64,
+    result_rows: u64,
+    memory_usage: u64,
+    user: String,
+    query: String,
+    exception: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueriesByTagInput {
+    connection: ClickHouseConnectionInput,
+    tag_key: String,
+    tag_value: String,
+    limit: Option<u32>,
+}
+
+/// Finds logged queries whose `log_comment` tags, as sent by `run_query`,
+/// contain `tag_key = tag_value`.
+#[tauri::command]
+pub(crate) async fn fetch_queries_by_tag(
+    input: QueriesByTagInput,
+) -> Result<Vec<QueryLogEntry>, HousecatError> {
+    let tag_key = require_value(&input.tag_key, "Tag key")?;
+    let limit = input.limit.unwrap_or(100).clamp(1, 1000);
+
+    let query = format!(
+        "SELECT {QUERY_LOG_COLUMNS} FROM system.query_log WHERE type != 'QueryStart' AND isValidJSON(log_comment) AND JSONExtractString(log_comment, {}) = {} ORDER BY event_time DESC LIMIT {limit} FORMAT JSON",
+        quote_string_literal(tag_key),
+        quote_string_literal(&input.tag_value)
+    );
+
+    fetch_rows(&input.connection, query)
+        .await
+        .map_err(|err| err.missing_table_as_unavailable("system.query_log"))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SlowQueriesInput {
+    connection: ClickHouseConnectionInput,
+    min_duration_ms: u64,
+    limit: Option<u32>,
+}
+
+/// Finished queries that ran longer than `min_duration_ms`, slowest first.
+#[tauri::command]
+pub(crate) async fn fetch_slow_queries(
+    input: SlowQueriesInput,
+) -> Result<Vec<QueryLogEntry>, HousecatError> {
+    let limit = input.limit.unwrap_or(100).clamp(1, 1000);
+
+    let query = format!(
+        "SELECT {QUERY_LOG_COLUMNS} FROM system.query_log WHERE type = 'QueryFinish' AND query_duration_ms > {} ORDER BY query_duration_ms DESC LIMIT {limit} FORMAT JSON",
+        input.min_duration_ms
+    );
+
+    fetch_rows(&input.connection, query)
+        .await
+        .map_err(|err| err.missing_table_as_unavailable("system.query_log"))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RecentQueryLogInput {
+    connection: ClickHouseConnectionInput,
+    min_duration_ms: Option<u64>,
+    min_memory_bytes: Option<u64>,
+    /// Only queries that failed, before or while running.
+    errors_only: Option<bool>,
+    limit: Option<u32>,
+}
+
+/// The latest completed or failed queries, newest first, optionally only
+/// those above a duration or memory threshold or that failed.
+#[tauri::command]
+pub(crate) async fn fetch_recent_query_log(
+    input: RecentQueryLogInput,
+) -> Result<Vec<QueryLogEntry>, HousecatError> {
+    let limit = input.limit.unwrap_or(100).clamp(1, 1000);
+
+    let mut conditions = vec!["type != 'QueryStart'".to_string()];
+    if let Some(min_duration_ms) = input.min_duration_ms {
+        conditions.push(format!("query_duration_ms >= {min_duration_ms}"));
+    }
+    if let Some(min_memory_bytes) = input.min_memory_bytes {
+        conditions.push(format!("memory_usage >= {min_memory_bytes}"));
+    }
+    if input.errors_only.unwrap_or(false) {
+        conditions.push("type IN ('ExceptionBeforeStart', 'ExceptionWhileProcessing')".to_string());
+    }
+
+    let query = format!(
+        "SELECT {QUERY_LOG_COLUMNS} FROM system.query_log WHERE {} ORDER BY event_time DESC LIMIT {limit} FORMAT JSON",
+        conditions.join(" AND ")
+    );
+
+    fetch_rows(&input.connection, query)
+        .await
+        .map_err(|err| err.missing_table_as_unavailable("system.query_log"))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProfileEventsInput {
+    connection: ClickHouseConnectionInput,
+    query_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseProfileEventsRow {
+    profile_events: BTreeMap<String, i64>,
+}
+
+/// The `ProfileEvents` counters of a finished query, such as `SelectedRows`
+/// or `DiskReadElapsedMicroseconds`, from its `system.query_log` entry.
+#[tauri::command]
+pub(crate) async fn fetch_profile_events(
+    input: ProfileEventsInput,
+) -> Result<BTreeMap<String, i64>, HousecatError> {
+    let query_id = require_value(&input.query_id, "Query id")?;
+
+    let query = format!(
+        "SELECT ProfileEvents AS profile_events FROM system.query_log WHERE query_id = {} AND type != 'QueryStart' ORDER BY event_time DESC LIMIT 1 FORMAT JSON",
+        quote_string_literal(query_id)
+    );
+
+    let rows: Vec<ClickHouseProfileEventsRow> = fetch_rows(&input.connection, query)
+        .await
+        .map_err(|err| err.missing_table_as_unavailable("system.query_log"))?;
+
+    rows.into_iter()
+        .next()
+        .map(|row| row.profile_events)
+        .ok_or_else(|| HousecatError::QueryNotLogged(query_id.to_string()))
+}
+
+/// `system.text_log` levels, most severe first, matching the order of the
+/// server's `level` enum.
+const LOG_LEVELS: &[&str] = &[
+    "Fatal",
+    "Critical",
+    "Error",
+    "Warning",
+    "Notice",
+    "Information",
+    "Debug",
+    "Trace",
+    "Test",
+];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ServerLogsInput {
+    connection: ClickHouseConnectionInput,
+    /// Least severe level to include; defaults to `Information`.
+    level: Option<String>,
+    /// Only entries at or after this time, in any form `normalize_datetime`
+    /// accepts.
+    since: Option<String>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct LogEntry {
+    event_time: String,
+    level: String,
+    logger_name: String,
+    message: String,
+    query_id: String,
+    thread_name: String,
+}
+
+/// Recent server log lines from `system.text_log`, newest first. The table
+/// only exists when `text_log` is enabled in the server configuration.
+#[tauri::command]
+pub(crate) async fn fetch_server_logs(
+    input: ServerLogsInput,
+) -> Result<Vec<LogEntry>, HousecatError> {
+    let level = input
+        .level
+        .as_deref()
+        .map(str::trim)
+        .filter(|level| !level.is_empty())
+        .unwrap_or("Information");
+    let level = LOG_LEVELS
+        .iter()
+        .find(|known| known.eq_ignore_ascii_case(level))
+        .ok_or_else(|| {
+            HousecatError::invalid_input(format!(
+                "Unknown log level `{level}`; expected one of {}",
+                LOG_LEVELS.join(", ")
+            ))
+        })?;
+    let limit = input.limit.unwrap_or(200).clamp(1, 5000);
+
+    let mut conditions = vec![format!("level <= {}", quote_string_literal(level))];
+    if let Some(since) = input
+        .since
+        .as_deref()
+        .filter(|since| !since.trim().is_empty())
+    {
+        // `event_time` is aliased to a string in the select list, so the
+        // filter goes on the underlying column.
+        conditions.push(format!(
+            "event_time_microseconds >= {}",
+            datetime::normalize(since, "DateTime64(6)")?
+        ));
+    }
+
+    let query = format!(
+        "SELECT toString(event_time_microseconds) AS event_time, toString(level) AS level, logger_name, message, query_id, thread_name FROM system.text_log WHERE {} ORDER BY event_time_microseconds DESC LIMIT {limit} FORMAT JSON",
+        conditions.join(" AND ")
+    );
+
+    fetch_rows(&input.connection, query)
+        .await
+        .map_err(|err| err.missing_table_as_unavailable("system.text_log"))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UnusedColumnsInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+    days: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseColumnUsageRow {
+    queries: u64,
+    used: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UnusedColumns {
+    columns: Vec<String>,
+    /// Logged queries that touched the table. With none, every column is
+    /// reported and the result says nothing.
+    queries_analyzed: u64,
+}
+
+/// Columns of a table that no finished query in the last `days` days
+/// referenced, according to the `columns` the server records per query in
+/// `system.query_log`. `SELECT *` is expanded there, so it counts as using
+/// every column. This is advisory only: the log covers this server alone,
+/// may have a shorter TTL than `days`, and misses queries run from sessions
+/// with `log_queries = 0`.
+#[tauri::command]
+pub(crate) async fn find_unused_columns(
+    input: UnusedColumnsInput,
+) -> Result<UnusedColumns, HousecatError> {
+    let schema_name = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+    let days = input.days.clamp(1, 365);
+
+    let columns = schema::fetch_table_columns(&input.connection, schema_name, table).await?;
+    if columns.is_empty() {
+        return Err(HousecatError::invalid_input(format!(
+            "Table {schema_name}.{table} does not exist"
+        )));
+    }
+
+    let qualified = format!("{schema_name}.{table}");
+    let query = format!(
+        "SELECT count() AS queries, groupUniqArrayArray(arrayFilter(column -> startsWith(column, {}), columns)) AS used FROM system.query_log WHERE type = 'QueryFinish' AND event_date >= today() - {days} AND has(tables, {}) FORMAT JSON",
+        quote_string_literal(&format!("{qualified}.")),
+        quote_string_literal(&qualified)
+    );
+    let usage: ClickHouseColumnUsageRow = fetch_rows(&input.connection, query)
+        .await
+        .map_err(|err| err.missing_table_as_unavailable("system.query_log"))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| HousecatError::Response("no usage row returned".to_string()))?;
+
+    let prefix_len = qualified.len() + 1;
+    let used: Vec<&str> = usage
+        .used
+        .iter()
+        .filter_map(|column| column.get(prefix_len..))
+        .collect();
+
+    Ok(UnusedColumns {
+        columns: columns
+            .into_iter()
+            .map(|column| column.name)
+            .filter(|name| !used.contains(&name.as_str()))
+            .collect(),
+        queries_analyzed: usage.queries,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReplicationQueueInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct ReplicationTask {
+    node_name: String,
+    #[serde(rename(deserialize = "type"))]
+    task_type: String,
+    create_time: String,
+    new_part_name: String,
+    num_tries: u64,
+    is_currently_executing: u8,
+    last_exception: String,
+    postpone_reason: String,
+}
+
+/// Pending tasks in a replicated table's replication queue, oldest first.
+/// Tasks with many tries and a `lastException` are what keep a replica
+/// behind. Tables that are not replicated have no queue and return nothing.
+#[tauri::command]
+pub(crate) async fn fetch_replication_queue(
+    input: ReplicationQueueInput,
+) -> Result<Vec<ReplicationTask>, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+
+    let query = format!(
+        "SELECT node_name, toString(type) AS type, toString(create_time) AS create_time, new_part_name, num_tries, is_currently_executing, last_exception, postpone_reason FROM system.replication_queue WHERE database = {} AND table = {} ORDER BY create_time FORMAT JSON",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+
+    fetch_rows(&input.connection, query).await
+}
@@ -0,0 +1,265 @@
+use crate::error::HousecatError;
+use crate::statement::{self, validate_where_clause};
+use crate::version::{compare_versions, parse_version, server_version};
+use crate::{
+    fetch_count, fetch_rows, qualified_table, quote_string_literal, require_confirmation,
+    require_value, run_clickhouse_query, ClickHouseConnectionInput,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MutationImpactInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+    where_clause: String,
+}
+
+async fn count_matching_rows(
+    connection: &ClickHouseConnectionInput,
+    schema: &str,
+    table: &str,
+    where_clause: &str,
+) -> Result<u64, HousecatError> {
+    let query = format!(
+        "SELECT count() AS count FROM {} WHERE ({where_clause}) FORMAT JSON",
+        qualified_table(schema, table)
+    );
+
+    fetch_count(connection, query).await
+}
+
+/// Counts the rows a `DELETE`/`UPDATE` with this condition would touch,
+/// without running the mutation.
+#[tauri::command]
+pub(crate) async fn estimate_mutation_impact(
+    input: MutationImpactInput,
+) -> Result<u64, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+    let where_clause = validate_where_clause(&input.where_clause)?;
+
+    count_matching_rows(&input.connection, schema, table, where_clause).await
+}
+
+/// First release with `DELETE FROM`, behind an experimental setting until
+/// 23.3 enabled it by default.
+const LIGHTWEIGHT_DELETE_VERSION: &[u32] = &[22, 8];
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseSettingValueRow {
+    name: String,
+    value: String,
+}
+
+/// Whether `DELETE FROM` can run for this user: the server must be new
+/// enough and the controlling setting on. Newer servers use
+/// `enable_lightweight_delete`; older ones only know the experimental flag.
+pub(crate) async fn lightweight_delete_supported(
+    connection: &ClickHouseConnectionInput,
+) -> Result<bool, HousecatError> {
+    let version = server_version(connection).await?;
+    let current = parse_version(&version)
+        .ok_or_else(|| HousecatError::Parse(format!("unrecognized server version `{version}`")))?;
+    if compare_versions(&current, LIGHTWEIGHT_DELETE_VERSION).is_lt() {
+        return Ok(false);
+    }
+
+    let settings: Vec<ClickHouseSettingValueRow> = fetch_rows(
+        connection,
+        "SELECT name, value FROM system.settings WHERE name IN ('enable_lightweight_delete', 'allow_experimental_lightweight_delete') FORMAT JSON"
+            .to_string(),
+    )
+    .await?;
+    let value = |name: &str| {
+        settings
+            .iter()
+            .find(|setting| setting.name == name)
+            .map(|setting| setting.value == "1")
+    };
+
+    Ok(value("enable_lightweight_delete")
+        .or_else(|| value("allow_experimental_lightweight_delete"))
+        .unwrap_or(false))
+}
+
+/// Lets the UI offer `DELETE FROM` where it works and fall back to
+/// `ALTER TABLE ... DELETE` elsewhere.
+#[tauri::command]
+pub(crate) async fn supports_lightweight_delete(
+    input: ClickHouseConnectionInput,
+) -> Result<bool, HousecatError> {
+    lightweight_delete_supported(&input).await
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LightweightDeleteInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+    where_clause: String,
+    confirmation: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LightweightDeleteResult {
+    estimated_rows: u64,
+    /// Matching rows before the delete minus those left after it, which
+    /// concurrent inserts into the table can skew.
+    deleted_rows: u64,
+}
+
+/// Conditions that match every row, which a cleanup should never need.
+fn matches_everything(where_clause: &str) -> bool {
+    matches!(
+        statement::tokens(where_clause)
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .as_slice(),
+        ["1"] | ["TRUE"] | ["1", "=", "1"] | ["(", "1", ")"] | ["(", "TRUE", ")"]
+    )
+}
+
+/// Deletes the rows matching `where_clause` with `DELETE FROM`, which
+/// hides them immediately instead of rewriting parts like
+/// `ALTER TABLE ... DELETE`. The confirmation must match the table name,
+/// and the matching rows are counted first so the result can report both
+/// the estimate and what was actually removed.
+#[tauri::command]
+pub(crate) async fn lightweight_delete(
+    input: LightweightDeleteInput,
+) -> Result<LightweightDeleteResult, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+    let where_clause = validate_where_clause(&input.where_clause)?;
+    require_confirmation(&input.confirmation, table)?;
+
+    if matches_everything(where_clause) {
+        return Err(HousecatError::invalid_input(
+            "The WHERE condition matches every row; use TRUNCATE to empty a table",
+        ));
+    }
+    if !lightweight_delete_supported(&input.connection).await? {
+        return Err(HousecatError::Unavailable("Lightweight DELETE".to_string()));
+    }
+
+    let estimated_rows =
+        count_matching_rows(&input.connection, schema, table, where_clause).await?;
+
+    let query = format!(
+        "DELETE FROM {} WHERE ({where_clause})",
+        qualified_table(schema, table)
+    );
+    run_clickhouse_query(&input.connection, query).await?;
+
+    let remaining_rows =
+        count_matching_rows(&input.connection, schema, table, where_clause).await?;
+
+    Ok(LightweightDeleteResult {
+        estimated_rows,
+        deleted_rows: estimated_rows.saturating_sub(remaining_rows),
+    })
+}
+
+/// Unfinished mutations a table may queue before it is a warning.
+const PENDING_MUTATIONS_WARNING: u64 = 3;
+
+/// Age, in seconds, of the oldest unfinished mutation that makes a table a
+/// warning, and a critical one.
+const PENDING_AGE_WARNING_SECS: u64 = 10 * 60;
+const PENDING_AGE_CRITICAL_SECS: u64 = 60 * 60;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MutationHealthInput {
+    connection: ClickHouseConnectionInput,
+    schema: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseMutationBacklogRow {
+    database: String,
+    table: String,
+    pending: u64,
+    failing: u64,
+    oldest_pending_secs: u64,
+    latest_fail_reason: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum HealthLevel {
+    Healthy,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MutationHealth {
+    database: String,
+    table: String,
+    health: HealthLevel,
+    pending_mutations: u64,
+    /// Unfinished mutations whose last attempt failed.
+    failing_mutations: u64,
+    /// Age of the oldest unfinished mutation; `None` when none is pending.
+    oldest_pending_secs: Option<u64>,
+    latest_fail_reason: Option<String>,
+}
+
+/// Any failing mutation, or one pending for over an hour, is critical: it
+/// blocks every later mutation on the table. A longer queue or one older
+/// than ten minutes is a warning; a short, fresh queue is normal.
+fn mutation_health_level(row: &ClickHouseMutationBacklogRow) -> HealthLevel {
+    if row.failing > 0 || row.oldest_pending_secs > PENDING_AGE_CRITICAL_SECS {
+        HealthLevel::Critical
+    } else if row.pending > PENDING_MUTATIONS_WARNING
+        || row.oldest_pending_secs > PENDING_AGE_WARNING_SECS
+    {
+        HealthLevel::Warning
+    } else {
+        HealthLevel::Healthy
+    }
+}
+
+/// Summarizes the mutation backlog of every table with mutations on
+/// record, optionally only in `schema`, so stuck mutations show up before
+/// anyone has to go looking.
+#[tauri::command]
+pub(crate) async fn mutation_health(
+    input: MutationHealthInput,
+) -> Result<Vec<MutationHealth>, HousecatError> {
+    let filter = match input.schema.as_deref().map(str::trim) {
+        Some(schema) if !schema.is_empty() => {
+            format!("WHERE database = {}", quote_string_literal(schema))
+        }
+        _ => String::new(),
+    };
+    let query = format!(
+        "SELECT database, table, countIf(NOT is_done) AS pending, \
+        countIf(NOT is_done AND latest_fail_reason != '') AS failing, \
+        if(pending = 0, 0, toUInt64(greatest(dateDiff('second', minIf(create_time, NOT is_done), now()), 0))) AS oldest_pending_secs, \
+        argMaxIf(latest_fail_reason, latest_fail_time, NOT is_done AND latest_fail_reason != '') AS latest_fail_reason \
+        FROM system.mutations {filter} GROUP BY database, table ORDER BY database, table FORMAT JSON"
+    );
+
+    let rows: Vec<ClickHouseMutationBacklogRow> = fetch_rows(&input.connection, query).await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| MutationHealth {
+            health: mutation_health_level(&row),
+            database: row.database,
+            table: row.table,
+            pending_mutations: row.pending,
+            failing_mutations: row.failing,
+            oldest_pending_secs: (row.pending > 0).then_some(row.oldest_pending_secs),
+            latest_fail_reason: Some(row.latest_fail_reason).filter(|reason| !reason.is_empty()),
+        })
+        .collect())
+}
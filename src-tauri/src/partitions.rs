@@ -0,0 +1,389 @@
+use crate::error::HousecatError;
+use crate::{
+    base_type, escape_identifier, fetch_rows, qualified_table, quote_string_literal,
+    require_confirmation, require_value, run_clickhouse_query, ClickHouseConnectionInput,
+};
+use crate::{schema, statement};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PartitionInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+    partition: Value,
+    confirmation: String,
+}
+
+/// Renders a partition value as a ClickHouse partition expression: strings
+/// are quoted, numbers are emitted verbatim and arrays become tuples, which
+/// matches how multi-column `PARTITION BY` keys are addressed.
+pub(crate) fn format_partition_expression(partition: &Value) -> Result<String, HousecatError> {
+    match partition {
+        Value::Array(items) if items.is_empty() => Ok("tuple()".to_string()),
+        Value::Array(items) => {
+            let elements = items
+                .iter()
+                .map(format_partition_element)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("({})", elements.join(", ")))
+        }
+        other => format_partition_element(other),
+    }
+}
+
+fn format_partition_element(value: &Value) -> Result<String, HousecatError> {
+    match value {
+        Value::String(text) => Ok(quote_string_literal(text)),
+        Value::Number(number) => Ok(number.to_string()),
+        _ => Err(HousecatError::invalid_input(
+            "Partition values must be strings, numbers or a flat list of them",
+        )),
+    }
+}
+
+async fn alter_partition(input: PartitionInput, action: &str) -> Result<(), HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+    require_confirmation(&input.confirmation, table)?;
+
+    let query = format!(
+        "ALTER TABLE {} {action} PARTITION {}",
+        qualified_table(schema, table),
+        format_partition_expression(&input.partition)?
+    );
+
+    run_clickhouse_query(&input.connection, query).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn detach_partition(input: PartitionInput) -> Result<(), HousecatError> {
+    alter_partition(input, "DETACH").await
+}
+
+#[tauri::command]
+pub(crate) async fn attach_partition(input: PartitionInput) -> Result<(), HousecatError> {
+    alter_partition(input, "ATTACH").await
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MovePartitionInput {
+    connection: ClickHouseConnectionInput,
+    src_schema: String,
+    src_table: String,
+    dst_schema: String,
+    dst_table: String,
+    partition: Value,
+    confirmation: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseTableKeysRow {
+    partition_key: String,
+    sorting_key: String,
+}
+
+async fn fetch_table_keys(
+    connection: &ClickHouseConnectionInput,
+    schema: &str,
+    table: &str,
+) -> Result<ClickHouseTableKeysRow, HousecatError> {
+    let query = format!(
+        "SELECT partition_key, sorting_key FROM system.tables WHERE database = {} AND name = {} FORMAT JSON",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+
+    fetch_rows(connection, query)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            HousecatError::invalid_input(format!("Table {schema}.{table} does not exist"))
+        })
+}
+
+/// `MOVE PARTITION ... TO TABLE` needs identical columns, partition key and
+/// sorting key, so check them up front for a readable error instead of
+/// ClickHouse's.
+async fn ensure_compatible_tables(
+    connection: &ClickHouseConnectionInput,
+    (src_schema, src_table): (&str, &str),
+    (dst_schema, dst_table): (&str, &str),
+) -> Result<(), HousecatError> {
+    let src_keys = fetch_table_keys(connection, src_schema, src_table).await?;
+    let dst_keys = fetch_table_keys(connection, dst_schema, dst_table).await?;
+
+    let src_columns = schema::fetch_table_columns(connection, src_schema, src_table).await?;
+    let dst_columns = schema::fetch_table_columns(connection, dst_schema, dst_table).await?;
+    let dst_name = format!("{dst_schema}.{dst_table}");
+    let drift = schema::diff_table(&dst_name, &src_columns, &dst_columns);
+
+    if drift.has_changes() {
+        return Err(HousecatError::IncompatibleTables(drift.summary()));
+    }
+    if src_columns != dst_columns {
+        return Err(HousecatError::IncompatibleTables(format!(
+            "columns of {dst_name} are in a different order"
+        )));
+    }
+    if src_keys.partition_key != dst_keys.partition_key {
+        return Err(HousecatError::IncompatibleTables(format!(
+            "partition key `{}` differs from `{}` in {dst_name}",
+            src_keys.partition_key, dst_keys.partition_key
+        )));
+    }
+    if src_keys.sorting_key != dst_keys.sorting_key {
+        return Err(HousecatError::IncompatibleTables(format!(
+            "sorting key `{}` differs from `{}` in {dst_name}",
+            src_keys.sorting_key, dst_keys.sorting_key
+        )));
+    }
+
+    Ok(())
+}
+
+/// Moves a partition into another table with the same structure, removing
+/// it from the source. The confirmation must match the source table name.
+#[tauri::command]
+pub(crate) async fn move_partition(input: MovePartitionInput) -> Result<(), HousecatError> {
+    let src_schema = require_value(&input.src_schema, "Source schema")?;
+    let src_table = require_value(&input.src_table, "Source table")?;
+    let dst_schema = require_value(&input.dst_schema, "Destination schema")?;
+    let dst_table = require_value(&input.dst_table, "Destination table")?;
+    require_confirmation(&input.confirmation, src_table)?;
+
+    if (src_schema, src_table) == (dst_schema, dst_table) {
+        return Err(HousecatError::invalid_input(
+            "Source and destination must be different tables",
+        ));
+    }
+
+    let partition = format_partition_expression(&input.partition)?;
+    ensure_compatible_tables(
+        &input.connection,
+        (src_schema, src_table),
+        (dst_schema, dst_table),
+    )
+    .await?;
+
+    let query = format!(
+        "ALTER TABLE {} MOVE PARTITION {partition} TO TABLE {}",
+        qualified_table(src_schema, src_table),
+        qualified_table(dst_schema, dst_table)
+    );
+
+    run_clickhouse_query(&input.connection, query).await?;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PartitionTableInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct PartitionCount {
+    partition: String,
+    partition_id: String,
+    rows: u64,
+    parts: u64,
+    bytes_on_disk: u64,
+}
+
+/// Row counts per partition from the active parts in `system.parts`, which
+/// needs no scan of the table data itself.
+#[tauri::command]
+pub(crate) async fn fetch_partition_counts(
+    input: PartitionTableInput,
+) -> Result<Vec<PartitionCount>, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+
+    // Partition values are strings here; ordering by length first keeps
+    // numeric partitions such as `9` and `10` in numeric order.
+    let query = format!(
+        "SELECT partition, partition_id, sum(rows) AS rows, count() AS parts, sum(bytes_on_disk) AS bytes_on_disk FROM system.parts WHERE active AND database = {} AND table = {} GROUP BY partition, partition_id ORDER BY length(partition), partition FORMAT JSON",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+
+    fetch_rows(&input.connection, query).await
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PartitionTimeRangesInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+    time_column: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct PartitionTimeRange {
+    partition_id: String,
+    min_time: String,
+    max_time: String,
+    rows: u64,
+}
+
+/// The earliest and latest value of `time_column` in each partition, for a
+/// timeline of where the data lies. When the partition key is built from
+/// that column, such as `toYYYYMM(event_date)`, the ranges come from the
+/// min/max index `system.parts` keeps per part, with no data read.
+/// Otherwise the column is scanned in full, which can be slow on large
+/// tables.
+#[tauri::command]
+pub(crate) async fn fetch_partition_time_ranges(
+    input: PartitionTimeRangesInput,
+) -> Result<Vec<PartitionTimeRange>, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+    let time_column = require_value(&input.time_column, "Time column")?;
+
+    let data_type = schema::fetch_column_type(&input.connection, schema, table, time_column)
+        .await?
+        .ok_or_else(|| {
+            HousecatError::invalid_input(format!(
+                "Column `{time_column}` does not exist in {schema}.{table}"
+            ))
+        })?;
+    let base = base_type(&data_type);
+    if !base.starts_with("Date") {
+        return Err(HousecatError::invalid_input(format!(
+            "Column `{time_column}` is {data_type}, not a Date or DateTime"
+        )));
+    }
+
+    let keys = fetch_table_keys(&input.connection, schema, table).await?;
+    let partitioned_by_column = statement::tokens(&keys.partition_key)
+        .iter()
+        .any(|token| token.eq_ignore_ascii_case(time_column));
+
+    let query = if partitioned_by_column {
+        // The part index keeps Date columns in `min_date`/`max_date` and
+        // DateTime columns in `min_time`/`max_time`.
+        let (min, max) = if base.starts_with("DateTime") {
+            ("min_time", "max_time")
+        } else {
+            ("min_date", "max_date")
+        };
+        format!(
+            "SELECT partition_id, toString(min({min})) AS min_time, toString(max({max})) AS max_time, sum(rows) AS rows FROM system.parts WHERE active AND database = {} AND table = {} GROUP BY partition_id ORDER BY min({min}) FORMAT JSON",
+            quote_string_literal(schema),
+            quote_string_literal(table)
+        )
+    } else {
+        format!(
+            "SELECT _partition_id AS partition_id, toString(min(`{column}`)) AS min_time, toString(max(`{column}`)) AS max_time, count() AS rows FROM {} GROUP BY partition_id ORDER BY min(`{column}`) FORMAT JSON",
+            qualified_table(schema, table),
+            column = escape_identifier(time_column)
+        )
+    };
+
+    fetch_rows(&input.connection, query).await
+}
+
+const MIB: u64 = 1024 * 1024;
+
+/// Upper bounds of the part size buckets; one more bucket holds everything
+/// above the last bound.
+const PART_SIZE_BOUNDS: &[(u64, &str)] = &[
+    (MIB, "< 1 MiB"),
+    (10 * MIB, "1-10 MiB"),
+    (100 * MIB, "10-100 MiB"),
+    (1024 * MIB, "100 MiB-1 GiB"),
+    (10 * 1024 * MIB, "1-10 GiB"),
+    (100 * 1024 * MIB, "10-100 GiB"),
+];
+
+#[derive(Debug, Deserialize)]
+struct ClickHousePartBucketRow {
+    bucket: usize,
+    parts: u64,
+    bytes_on_disk: u64,
+    rows: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PartSizeBucket {
+    label: String,
+    min_bytes: u64,
+    /// Exclusive; `None` for the open-ended last bucket.
+    max_bytes: Option<u64>,
+    parts: u64,
+    bytes_on_disk: u64,
+    rows: u64,
+}
+
+/// Active parts of a table bucketed by size on disk, smallest first, with
+/// empty buckets included so the histogram keeps its shape. Merges should
+/// leave a few large parts; many small ones mean merges are falling behind
+/// inserts.
+#[tauri::command]
+pub(crate) async fn fetch_part_size_distribution(
+    input: PartitionTableInput,
+) -> Result<Vec<PartSizeBucket>, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+
+    let bounds: Vec<String> = PART_SIZE_BOUNDS
+        .iter()
+        .map(|(bound, _)| bound.to_string())
+        .collect();
+    // `arrayFirstIndex` is 1-based and 0 when no bound is larger, which is
+    // the open-ended bucket.
+    let query = format!(
+        "SELECT arrayFirstIndex(bound -> bytes_on_disk < bound, [{}]) AS bucket, count() AS parts, sum(bytes_on_disk) AS bytes_on_disk, sum(rows) AS rows FROM system.parts WHERE active AND database = {} AND table = {} GROUP BY bucket FORMAT JSON",
+        bounds.join(", "),
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+    let rows: Vec<ClickHousePartBucketRow> = fetch_rows(&input.connection, query).await?;
+
+    let mut buckets: Vec<PartSizeBucket> = PART_SIZE_BOUNDS
+        .iter()
+        .enumerate()
+        .map(|(index, (bound, label))| PartSizeBucket {
+            label: label.to_string(),
+            min_bytes: index
+                .checked_sub(1)
+                .map_or(0, |previous| PART_SIZE_BOUNDS[previous].0),
+            max_bytes: Some(*bound),
+            parts: 0,
+            bytes_on_disk: 0,
+            rows: 0,
+        })
+        .collect();
+    let largest = PART_SIZE_BOUNDS[PART_SIZE_BOUNDS.len() - 1].0;
+    buckets.push(PartSizeBucket {
+        label: "> 100 GiB".to_string(),
+        min_bytes: largest,
+        max_bytes: None,
+        parts: 0,
+        bytes_on_disk: 0,
+        rows: 0,
+    });
+
+    for row in rows {
+        let index = row.bucket.checked_sub(1).unwrap_or(PART_SIZE_BOUNDS.len());
+        if let Some(bucket) = buckets.get_mut(index) {
+            bucket.parts = row.parts;
+            bucket.bytes_on_disk = row.bytes_on_disk;
+            bucket.rows = row.rows;
+        }
+    }
+
+    Ok(buckets)
+}
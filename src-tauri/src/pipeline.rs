@@ -0,0 +1,182 @@
+use crate::error::HousecatError;
+use crate::statement::require_read_only;
+use crate::{fetch_rows, ClickHouseConnectionInput};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PipelineInput {
+    connection: ClickHouseConnectionInput,
+    query: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseExplainRow {
+    explain: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PipelineNode {
+    id: String,
+    /// Processor name, such as `ExpressionTransform`.
+    label: String,
+    /// The query plan step the processor belongs to, when grouped.
+    group: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PipelineEdge {
+    from: String,
+    to: String,
+    /// Parallelism such as `× 4` when several ports are merged into one edge.
+    label: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PipelineGraph {
+    nodes: Vec<PipelineNode>,
+    edges: Vec<PipelineEdge>,
+}
+
+/// The quoted value of `name=` in a DOT attribute list, unescaped.
+fn dot_attribute(line: &str, name: &str) -> Option<String> {
+    let mut rest = line;
+    loop {
+        let start = rest.find(name)?;
+        let after = rest[start + name.len()..].trim_start();
+        rest = &rest[start + name.len()..];
+
+        let Some(after) = after.strip_prefix('=') else {
+            continue;
+        };
+        let after = after.trim_start().strip_prefix('"')?;
+
+        let mut value = String::new();
+        let mut chars = after.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => value.extend(chars.next()),
+                '"' => return Some(value),
+                _ => value.push(c),
+            }
+        }
+        return None;
+    }
+}
+
+fn non_empty(text: Option<String>) -> Option<String> {
+    text.filter(|text| !text.trim().is_empty())
+}
+
+/// Parses the DOT emitted by `EXPLAIN PIPELINE graph = 1`: processors are
+/// `nN [label="..."]` nodes, optionally nested in `subgraph cluster_N`
+/// blocks labelled with their plan step, and `nA -> nB` lines are edges.
+fn parse_pipeline_dot(dot: &str) -> PipelineGraph {
+    let mut graph = PipelineGraph::default();
+    // One entry per open brace: `Some` for a cluster, holding its label once
+    // seen, `None` for any other block.
+    let mut scopes: Vec<Option<Option<String>>> = Vec::new();
+
+    for line in dot.lines().map(str::trim) {
+        let structure = strip_quoted(line);
+        let opens = structure.matches('{').count();
+        let closes = structure.matches('}').count();
+
+        for index in 0..opens {
+            let is_cluster = index == 0 && line.starts_with("subgraph cluster");
+            scopes.push(is_cluster.then_some(None));
+        }
+        parse_pipeline_line(line, &mut scopes, &mut graph);
+        for _ in 0..closes {
+            scopes.pop();
+        }
+    }
+
+    graph
+}
+
+/// `line` with its double-quoted strings removed, so braces inside labels
+/// are not mistaken for blocks.
+fn strip_quoted(line: &str) -> String {
+    let mut stripped = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_quotes => {
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            _ if !in_quotes => stripped.push(c),
+            _ => {}
+        }
+    }
+    stripped
+}
+
+fn parse_pipeline_line(
+    line: &str,
+    scopes: &mut [Option<Option<String>>],
+    graph: &mut PipelineGraph,
+) {
+    let line = line.trim_start_matches(['{', ' ']);
+
+    if let Some((from, rest)) = line.split_once("->") {
+        let to = rest
+            .trim()
+            .split([' ', '[', ';'])
+            .next()
+            .unwrap_or_default();
+        graph.edges.push(PipelineEdge {
+            from: from.trim().to_string(),
+            to: to.to_string(),
+            label: non_empty(dot_attribute(rest, "label")),
+        });
+        return;
+    }
+
+    if line.starts_with("label") {
+        if let Some(Some(label)) = scopes.last_mut() {
+            *label = non_empty(dot_attribute(line, "label"));
+        }
+        return;
+    }
+
+    let id = line.split([' ', '[', ';']).next().unwrap_or_default();
+    let is_node_id =
+        id.len() > 1 && id.starts_with('n') && id[1..].chars().all(|c| c.is_ascii_digit());
+    if is_node_id {
+        let group = scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.as_ref())
+            .cloned()
+            .flatten();
+        graph.nodes.push(PipelineNode {
+            id: id.to_string(),
+            label: dot_attribute(line, "label").unwrap_or_else(|| id.to_string()),
+            group,
+        });
+    }
+}
+
+/// Runs `EXPLAIN PIPELINE graph = 1` and returns the processor graph as
+/// nodes and edges, so the UI can draw it.
+#[tauri::command]
+pub(crate) async fn explain_pipeline_graph(
+    input: PipelineInput,
+) -> Result<PipelineGraph, HousecatError> {
+    let query = require_read_only(&input.query)?;
+
+    let rows: Vec<ClickHouseExplainRow> = fetch_rows(
+        &input.connection,
+        format!("EXPLAIN PIPELINE graph = 1 {query} FORMAT JSON"),
+    )
+    .await?;
+
+    let dot: Vec<String> = rows.into_iter().map(|row| row.explain).collect();
+    Ok(parse_pipeline_dot(&dot.join("\n")))
+}
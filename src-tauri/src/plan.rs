@@ -0,0 +1,183 @@
+use crate::error::HousecatError;
+use crate::statement::require_read_only;
+use crate::{fetch_rows_with_params, settings, ClickHouseConnectionInput};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum ExplainMode {
+    Ast,
+    Syntax,
+    Plan,
+    Pipeline,
+    Estimate,
+}
+
+impl ExplainMode {
+    fn keyword(self) -> &'static str {
+        match self {
+            Self::Ast => "AST",
+            Self::Syntax => "SYNTAX",
+            Self::Plan => "PLAN",
+            Self::Pipeline => "PIPELINE",
+            Self::Estimate => "ESTIMATE",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExplainQueryInput {
+    connection: ClickHouseConnectionInput,
+    query: String,
+    mode: ExplainMode,
+    /// `EXPLAIN` options such as `indexes = 1` or `actions = 1` for `PLAN`.
+    options: Option<BTreeMap<String, String>>,
+    /// Per-query settings, validated like `run_query`'s, so the plan is
+    /// the one the query would get when run with them.
+    settings: Option<BTreeMap<String, String>>,
+    /// Values for `{name:Type}` placeholders, as for `run_query`.
+    parameters: Option<BTreeMap<String, String>>,
+}
+
+/// One line of an indented `EXPLAIN` output, with the lines indented
+/// below it as children.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExplainNode {
+    text: String,
+    children: Vec<ExplainNode>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExplainResult {
+    mode: ExplainMode,
+    /// The output as ClickHouse printed it.
+    text: String,
+    /// `text` as a tree by indentation; empty for `ESTIMATE`.
+    nodes: Vec<ExplainNode>,
+    /// Per-table estimates of `ESTIMATE`, one object per row.
+    rows: Option<Vec<Map<String, Value>>>,
+}
+
+/// `name = value` pairs for the `EXPLAIN` clause. Both sides are spliced
+/// into the SQL, so only plain names and numbers are accepted.
+fn render_options(options: Option<&BTreeMap<String, String>>) -> Result<String, HousecatError> {
+    let Some(options) = options else {
+        return Ok(String::new());
+    };
+
+    let rendered = options
+        .iter()
+        .map(|(name, value)| {
+            let name = name.trim();
+            let value = value.trim();
+            let name_ok =
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            let value_ok = !value.is_empty() && value.chars().all(|c| c.is_ascii_digit());
+            if !name_ok || !value_ok {
+                return Err(HousecatError::invalid_input(format!(
+                    "`{name} = {value}` is not a valid EXPLAIN option"
+                )));
+            }
+            Ok(format!("{name} = {value}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(rendered.join(", "))
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Builds the subtree of lines indented deeper than `parent`, starting at
+/// `lines[*next]`.
+fn build_tree(lines: &[&str], next: &mut usize, parent: Option<usize>) -> Vec<ExplainNode> {
+    let mut nodes = Vec::new();
+
+    while let Some(line) = lines.get(*next) {
+        let indent = indentation(line);
+        if parent.is_some_and(|parent| indent <= parent) {
+            break;
+        }
+
+        *next += 1;
+        let children = build_tree(lines, next, Some(indent));
+        nodes.push(ExplainNode {
+            text: line.trim().to_string(),
+            children,
+        });
+    }
+
+    nodes
+}
+
+fn explain_tree(text: &str) -> Vec<ExplainNode> {
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+
+    build_tree(&lines, &mut 0, None)
+}
+
+/// Runs the query through `EXPLAIN` in the chosen mode, with the same
+/// settings and parameters it would run with, and returns the output both
+/// as text and as a tree. Only read-only statements are accepted.
+#[tauri::command]
+pub(crate) async fn explain_query(
+    input: ExplainQueryInput,
+) -> Result<ExplainResult, HousecatError> {
+    let query = require_read_only(&input.query)?;
+    let options = render_options(input.options.as_ref())?;
+
+    let query_parameters = settings::query_parameter_params(input.parameters.as_ref())?;
+    let mut params = settings::settings_params(input.settings.as_ref())?;
+    params.extend(
+        query_parameters
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone())),
+    );
+
+    let rows: Vec<Map<String, Value>> = fetch_rows_with_params(
+        &input.connection,
+        format!(
+            "EXPLAIN {} {options} {query} FORMAT JSON",
+            input.mode.keyword()
+        ),
+        &params,
+    )
+    .await?;
+
+    if let ExplainMode::Estimate = input.mode {
+        let text = rows
+            .iter()
+            .map(|row| Value::Object(row.clone()).to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        return Ok(ExplainResult {
+            mode: input.mode,
+            text,
+            nodes: Vec::new(),
+            rows: Some(rows),
+        });
+    }
+
+    let text = rows
+        .iter()
+        .filter_map(|row| row.get("explain").and_then(Value::as_str))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let nodes = explain_tree(&text);
+
+    Ok(ExplainResult {
+        mode: input.mode,
+        text,
+        nodes,
+        rows: None,
+    })
+}
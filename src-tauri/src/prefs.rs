@@ -0,0 +1,137 @@
+use crate::error::HousecatError;
+use crate::require_value;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const PREFS_FILE: &str = "table-view-prefs.json";
+
+/// Serializes read-modify-write cycles on the prefs file, so two saves in
+/// quick succession cannot drop each other's changes.
+static PREFS_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ColumnFilter {
+    column: String,
+    operator: String,
+    value: Value,
+}
+
+/// How a table was last browsed. Every field is optional so the frontend
+/// can add preferences without invalidating ones saved earlier.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct TableViewPrefs {
+    column_order: Vec<String>,
+    sort_column: Option<String>,
+    sort_direction: Option<String>,
+    filters: Vec<ColumnFilter>,
+    page_size: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TableViewKeyInput {
+    /// Identifies the saved connection, such as its name in the connection
+    /// list; never the credentials themselves.
+    connection_key: String,
+    schema: String,
+    table: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SaveTableViewPrefsInput {
+    #[serde(flatten)]
+    key: TableViewKeyInput,
+    prefs: TableViewPrefs,
+}
+
+impl TableViewKeyInput {
+    fn storage_key(&self) -> Result<String, HousecatError> {
+        let connection_key = require_value(&self.connection_key, "Connection key")?;
+        let schema = require_value(&self.schema, "Schema")?;
+        let table = require_value(&self.table, "Table")?;
+
+        // JSON-encoded so no choice of names can make two keys collide.
+        serde_json::to_string(&[connection_key, schema, table])
+            .map_err(|err| HousecatError::Io(format!("Could not build prefs key: {err}")))
+    }
+}
+
+fn prefs_path(app: &AppHandle) -> Result<PathBuf, HousecatError> {
+    let directory = app
+        .path()
+        .app_data_dir()
+        .map_err(|err| HousecatError::Io(format!("Could not locate app data: {err}")))?;
+
+    Ok(directory.join(PREFS_FILE))
+}
+
+/// Reads every saved view. A missing file means nothing was saved yet; an
+/// unreadable one is reported rather than silently overwritten.
+fn read_all(path: &Path) -> Result<BTreeMap<String, TableViewPrefs>, HousecatError> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(err) => {
+            return Err(HousecatError::Io(format!(
+                "Could not read {}: {err}",
+                path.display()
+            )))
+        }
+    };
+
+    serde_json::from_str(&contents)
+        .map_err(|err| HousecatError::Io(format!("Could not parse {}: {err}", path.display())))
+}
+
+/// Writes to a temporary file first, so a crash mid-write leaves the old
+/// preferences intact.
+fn write_all(path: &Path, prefs: &BTreeMap<String, TableViewPrefs>) -> Result<(), HousecatError> {
+    let write_error = |err: std::io::Error| {
+        HousecatError::Io(format!("Could not write {}: {err}", path.display()))
+    };
+
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory).map_err(write_error)?;
+    }
+    let contents = serde_json::to_string_pretty(prefs)
+        .map_err(|err| HousecatError::Io(format!("Could not serialize prefs: {err}")))?;
+    let temporary = path.with_extension("json.tmp");
+    std::fs::write(&temporary, contents).map_err(write_error)?;
+    std::fs::rename(&temporary, path).map_err(write_error)
+}
+
+/// Saves how a table is being browsed, keyed by connection and table, in
+/// the app's data directory. Nothing is sent to ClickHouse.
+#[tauri::command]
+pub(crate) fn save_table_view_prefs(
+    app: AppHandle,
+    input: SaveTableViewPrefsInput,
+) -> Result<(), HousecatError> {
+    let key = input.key.storage_key()?;
+    let path = prefs_path(&app)?;
+
+    let _guard = PREFS_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut prefs = read_all(&path)?;
+    prefs.insert(key, input.prefs);
+    write_all(&path, &prefs)
+}
+
+/// The saved view of a table, or `None` when none was saved.
+#[tauri::command]
+pub(crate) fn load_table_view_prefs(
+    app: AppHandle,
+    input: TableViewKeyInput,
+) -> Result<Option<TableViewPrefs>, HousecatError> {
+    let key = input.storage_key()?;
+    let path = prefs_path(&app)?;
+
+    let _guard = PREFS_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    Ok(read_all(&path)?.remove(&key))
+}
@@ -0,0 +1,85 @@
+use crate::{fetch_rows, quote_string_literal, ClickHouseConnectionInput};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseProcessProgress {
+    read_rows: u64,
+    read_bytes: u64,
+    total_rows_approx: u64,
+    elapsed: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryStage {
+    query_id: String,
+    read_rows: u64,
+    read_bytes: u64,
+    total_rows_approx: u64,
+    elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryStarted {
+    query_id: String,
+}
+
+/// Emits a `query-started` event as a query is sent, so the UI holds its id
+/// for `cancel_query` while the command is still waiting for results.
+pub(crate) fn emit_query_started(app: &AppHandle, query_id: &str) {
+    let _ = app.emit(
+        "query-started",
+        QueryStarted {
+            query_id: query_id.to_string(),
+        },
+    );
+}
+
+/// Polls `system.processes` for `query_id` and emits a `query-stage` event
+/// per poll until the returned handle is aborted by the caller once the
+/// query has finished.
+pub(crate) fn spawn_progress_poller(
+    app: AppHandle,
+    mut connection: ClickHouseConnectionInput,
+    query_id: String,
+) -> JoinHandle<()> {
+    // A session only serves one request at a time, so polling within the
+    // query's own session would be rejected as locked.
+    connection.session_id = None;
+
+    let query = format!(
+        "SELECT read_rows, read_bytes, total_rows_approx, elapsed FROM system.processes WHERE query_id = {} FORMAT JSON",
+        quote_string_literal(&query_id)
+    );
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            let Ok(rows) =
+                fetch_rows::<ClickHouseProcessProgress>(&connection, query.clone()).await
+            else {
+                continue;
+            };
+
+            if let Some(row) = rows.into_iter().next() {
+                let _ = app.emit(
+                    "query-stage",
+                    QueryStage {
+                        query_id: query_id.clone(),
+                        read_rows: row.read_rows,
+                        read_bytes: row.read_bytes,
+                        total_rows_approx: row.total_rows_approx,
+                        elapsed_ms: (row.elapsed * 1000.0) as u64,
+                    },
+                );
+            }
+        }
+    })
+}
@@ -0,0 +1,60 @@
+use crate::error::HousecatError;
+use crate::statement::{clean_statement, leading_keyword, non_deterministic_functions};
+use crate::{fetch_count, ClickHouseConnectionInput};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueryCacheInput {
+    connection: ClickHouseConnectionInput,
+    query: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueryCacheEligibility {
+    eligible: bool,
+    /// Why the query cannot be cached; empty when it can.
+    reasons: Vec<String>,
+}
+
+/// Checks whether ClickHouse's query cache (23.1+) would accept `query`:
+/// the server must support it and the query must be a `SELECT` that calls
+/// no non-deterministic function and reads no system table, which the
+/// cache rejects by default. Whether the result fits under
+/// `query_cache_max_entry_size_in_bytes` is only known once it has run.
+#[tauri::command]
+pub(crate) async fn query_cache_eligible(
+    input: QueryCacheInput,
+) -> Result<QueryCacheEligibility, HousecatError> {
+    let query = clean_statement(&input.query)?;
+    let mut reasons = Vec::new();
+
+    if !matches!(leading_keyword(query).as_str(), "SELECT" | "WITH") {
+        reasons.push("Only SELECT queries are cached".to_string());
+    }
+
+    for function in non_deterministic_functions(query) {
+        reasons.push(format!("Calls non-deterministic function {function}()"));
+    }
+
+    if query.to_ascii_lowercase().contains("system.") {
+        reasons.push("Reads a system table".to_string());
+    }
+
+    let supported = fetch_count(
+        &input.connection,
+        "SELECT count() AS count FROM system.settings WHERE name = 'use_query_cache' FORMAT JSON"
+            .to_string(),
+    )
+    .await?
+        > 0;
+    if !supported {
+        reasons.push("The server does not have a query cache".to_string());
+    }
+
+    Ok(QueryCacheEligibility {
+        eligible: reasons.is_empty(),
+        reasons,
+    })
+}
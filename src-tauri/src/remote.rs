@@ -0,0 +1,89 @@
+use crate::error::HousecatError;
+use crate::{
+    parse_preview_body, quote_string_literal, require_value, run_clickhouse_query_with_params,
+    ClickHouseConnectionInput, TablePreview, QUOTE_DENORMALS_SETTING,
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RemotePreviewInput {
+    connection: ClickHouseConnectionInput,
+    /// `host` or `host:port` of the remote server's native protocol.
+    remote_host: String,
+    /// `database.table` on the remote server.
+    remote_table: String,
+    remote_user: Option<String>,
+    remote_password: Option<String>,
+    limit: Option<u32>,
+}
+
+/// Accepts host names, IPv4 addresses and bracketed IPv6 addresses, each
+/// with an optional port. Anything else is rejected before it reaches SQL.
+fn validate_remote_host(host: &str) -> Result<&str, HousecatError> {
+    let valid = host
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ':' | '[' | ']'));
+
+    if !valid {
+        return Err(HousecatError::invalid_input(format!(
+            "`{host}` is not a valid remote host"
+        )));
+    }
+
+    Ok(host)
+}
+
+fn split_remote_table(remote_table: &str) -> Result<(&str, &str), HousecatError> {
+    match remote_table.split_once('.') {
+        Some((database, table)) if !database.trim().is_empty() && !table.trim().is_empty() => {
+            Ok((database.trim(), table.trim()))
+        }
+        _ => Err(HousecatError::invalid_input(
+            "Remote table must be given as database.table",
+        )),
+    }
+}
+
+/// Previews a table on another server through the `remote()` table
+/// function, so the current connection's server does the fetching.
+#[tauri::command]
+pub(crate) async fn preview_remote(
+    input: RemotePreviewInput,
+) -> Result<TablePreview, HousecatError> {
+    let host = validate_remote_host(require_value(&input.remote_host, "Remote host")?)?;
+    let (database, table) =
+        split_remote_table(require_value(&input.remote_table, "Remote table")?)?;
+    let limit = input.limit.unwrap_or(200).clamp(1, 1000);
+
+    let mut arguments = vec![
+        quote_string_literal(host),
+        quote_string_literal(database),
+        quote_string_literal(table),
+    ];
+    if let Some(user) = input.remote_user.as_deref().map(str::trim) {
+        if !user.is_empty() {
+            arguments.push(quote_string_literal(user));
+            arguments.push(quote_string_literal(
+                input.remote_password.as_deref().unwrap_or_default(),
+            ));
+        }
+    }
+
+    let query = format!(
+        "SELECT * FROM remote({}) LIMIT {limit} FORMAT JSON",
+        arguments.join(", ")
+    );
+
+    let body = run_clickhouse_query_with_params(
+        &input.connection,
+        query,
+        &[(QUOTE_DENORMALS_SETTING, "1".to_string())],
+    )
+    .await?
+    .text()
+    .await
+    .map_err(|err| HousecatError::Response(err.to_string()))?;
+
+    Ok(parse_preview_body(&body))
+}
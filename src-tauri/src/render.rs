@@ -0,0 +1,142 @@
+use crate::error::HousecatError;
+use crate::{fetch_query_preview, ClickHouseConnectionInput, TablePreview};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Rendered tables are meant for pasting into documents, so they are kept
+/// far below the query editor's own row limit.
+const MAX_RENDERED_ROWS: usize = 200;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RenderQueryInput {
+    connection: ClickHouseConnectionInput,
+    query: String,
+}
+
+/// Fetches one row past the cap so truncation can be reported.
+async fn fetch_rendered_rows(
+    input: &RenderQueryInput,
+) -> Result<(TablePreview, bool), HousecatError> {
+    let mut preview = fetch_query_preview(
+        &input.connection,
+        &input.query,
+        MAX_RENDERED_ROWS as u32 + 1,
+    )
+    .await?;
+
+    let truncated = preview.rows.len() > MAX_RENDERED_ROWS;
+    preview.rows.truncate(MAX_RENDERED_ROWS);
+
+    Ok((preview, truncated))
+}
+
+fn cell_text(row: &Value, column: &str) -> String {
+    match row.get(column) {
+        None | Some(Value::Null) => "NULL".to_string(),
+        Some(Value::String(text)) => text.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", " ")
+        .replace(['\n', '\r'], " ")
+}
+
+fn render_markdown(preview: &TablePreview, truncated: bool) -> String {
+    let header: Vec<String> = preview
+        .columns
+        .iter()
+        .map(|column| markdown_cell(&column.name))
+        .collect();
+
+    let mut lines = vec![
+        format!("| {} |", header.join(" | ")),
+        format!("|{}|", vec![" --- "; header.len()].join("|")),
+    ];
+
+    for row in &preview.rows {
+        let cells: Vec<String> = preview
+            .columns
+            .iter()
+            .map(|column| markdown_cell(&cell_text(row, &column.name)))
+            .collect();
+        lines.push(format!("| {} |", cells.join(" | ")));
+    }
+
+    if truncated {
+        lines.push(String::new());
+        lines.push(format!("_Showing the first {MAX_RENDERED_ROWS} rows._"));
+    }
+
+    lines.join("\n")
+}
+
+#[tauri::command]
+pub(crate) async fn export_query_markdown(
+    input: RenderQueryInput,
+) -> Result<String, HousecatError> {
+    let (preview, truncated) = fetch_rendered_rows(&input).await?;
+    Ok(render_markdown(&preview, truncated))
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Inline styles only, since wikis and mail clients drop `<style>` blocks.
+const HTML_TABLE_STYLE: &str =
+    "border-collapse: collapse; font-family: sans-serif; font-size: 13px;";
+const HTML_CELL_STYLE: &str = "border: 1px solid #ccc; padding: 4px 8px; text-align: left;";
+
+fn render_html(preview: &TablePreview, truncated: bool) -> String {
+    let mut lines = vec![
+        format!("<table style=\"{HTML_TABLE_STYLE}\">"),
+        "  <thead>".to_string(),
+        "    <tr>".to_string(),
+    ];
+    for column in &preview.columns {
+        lines.push(format!(
+            "      <th style=\"{HTML_CELL_STYLE} background: #f4f4f4;\">{}</th>",
+            html_escape(&column.name)
+        ));
+    }
+    lines.push("    </tr>".to_string());
+    lines.push("  </thead>".to_string());
+    lines.push("  <tbody>".to_string());
+
+    for row in &preview.rows {
+        lines.push("    <tr>".to_string());
+        for column in &preview.columns {
+            lines.push(format!(
+                "      <td style=\"{HTML_CELL_STYLE}\">{}</td>",
+                html_escape(&cell_text(row, &column.name))
+            ));
+        }
+        lines.push("    </tr>".to_string());
+    }
+
+    lines.push("  </tbody>".to_string());
+    lines.push("</table>".to_string());
+
+    if truncated {
+        lines.push(format!(
+            "<p><em>Showing the first {MAX_RENDERED_ROWS} rows.</em></p>"
+        ));
+    }
+
+    lines.join("\n")
+}
+
+#[tauri::command]
+pub(crate) async fn export_query_html(input: RenderQueryInput) -> Result<String, HousecatError> {
+    let (preview, truncated) = fetch_rendered_rows(&input).await?;
+    Ok(render_html(&preview, truncated))
+}
@@ -0,0 +1,697 @@
+use crate::error::HousecatError;
+use crate::{
+    base_type, fetch_count, fetch_rows, qualified_table, quote_string_literal, require_value,
+    ClickHouseConnectionInput,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseColumnRow {
+    table: String,
+    name: String,
+    #[serde(rename = "type")]
+    data_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TableRefInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct ColumnDetail {
+    position: u64,
+    name: String,
+    #[serde(rename(deserialize = "type"))]
+    data_type: String,
+    default_kind: String,
+    default_expression: String,
+    comment: String,
+    compression_codec: String,
+}
+
+/// A column the server fills in itself when an insert omits it. `MATERIALIZED`
+/// and `ALIAS` columns cannot be inserted into at all.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct ColumnDefault {
+    name: String,
+    #[serde(rename(deserialize = "type"))]
+    data_type: String,
+    default_kind: String,
+    default_expression: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SnapshotSchemaInput {
+    connection: ClickHouseConnectionInput,
+    database: String,
+    path: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SchemaDriftInput {
+    connection: ClickHouseConnectionInput,
+    database: String,
+    snapshot: SchemaSnapshot,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CompareSchemasInput {
+    connection_a: ClickHouseConnectionInput,
+    connection_b: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SnapshotColumn {
+    pub(crate) name: String,
+    data_type: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SchemaSnapshot {
+    database: String,
+    captured_at_unix: u64,
+    tables: BTreeMap<String, Vec<SnapshotColumn>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ColumnTypeChange {
+    name: String,
+    snapshot_type: String,
+    current_type: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TableDrift {
+    table: String,
+    added_columns: Vec<SnapshotColumn>,
+    removed_columns: Vec<SnapshotColumn>,
+    changed_columns: Vec<ColumnTypeChange>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SchemaDrift {
+    added_tables: Vec<String>,
+    removed_tables: Vec<String>,
+    changed_tables: Vec<TableDrift>,
+}
+
+impl TableDrift {
+    pub(crate) fn has_changes(&self) -> bool {
+        !self.added_columns.is_empty()
+            || !self.removed_columns.is_empty()
+            || !self.changed_columns.is_empty()
+    }
+
+    /// One-line description of the differences, for error messages.
+    pub(crate) fn summary(&self) -> String {
+        let names = |columns: &[SnapshotColumn]| {
+            columns
+                .iter()
+                .map(|column| column.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut parts = Vec::new();
+        if !self.added_columns.is_empty() {
+            parts.push(format!(
+                "only in {}: {}",
+                self.table,
+                names(&self.added_columns)
+            ));
+        }
+        if !self.removed_columns.is_empty() {
+            parts.push(format!(
+                "missing from {}: {}",
+                self.table,
+                names(&self.removed_columns)
+            ));
+        }
+        for change in &self.changed_columns {
+            parts.push(format!(
+                "{} is {} but {} in {}",
+                change.name, change.snapshot_type, change.current_type, self.table
+            ));
+        }
+
+        parts.join("; ")
+    }
+}
+
+pub(crate) async fn table_exists_in(
+    connection: &ClickHouseConnectionInput,
+    schema: &str,
+    table: &str,
+) -> Result<bool, HousecatError> {
+    let query = format!(
+        "SELECT count() AS count FROM system.tables WHERE database = {} AND name = {} FORMAT JSON",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+
+    Ok(fetch_count(connection, query).await? > 0)
+}
+
+/// Fails with [`HousecatError::TableAlreadyExists`] when the target of a
+/// create, clone or rename is taken, before ClickHouse is asked to do it.
+pub(crate) async fn ensure_table_absent(
+    connection: &ClickHouseConnectionInput,
+    schema: &str,
+    table: &str,
+) -> Result<(), HousecatError> {
+    if table_exists_in(connection, schema, table).await? {
+        return Err(HousecatError::TableAlreadyExists(format!(
+            "{schema}.{table}"
+        )));
+    }
+
+    Ok(())
+}
+
+async fn fetch_database_columns(
+    connection: &ClickHouseConnectionInput,
+    database: &str,
+) -> Result<BTreeMap<String, Vec<SnapshotColumn>>, HousecatError> {
+    let query = format!(
+        "SELECT table, name, type FROM system.columns WHERE database = {} ORDER BY table, position FORMAT JSON",
+        quote_string_literal(database)
+    );
+
+    let rows: Vec<ClickHouseColumnRow> = fetch_rows(connection, query).await?;
+
+    let mut tables: BTreeMap<String, Vec<SnapshotColumn>> = BTreeMap::new();
+    for row in rows {
+        tables.entry(row.table).or_default().push(SnapshotColumn {
+            name: row.name,
+            data_type: row.data_type,
+        });
+    }
+
+    Ok(tables)
+}
+
+/// Columns of one table in definition order.
+pub(crate) async fn fetch_table_columns(
+    connection: &ClickHouseConnectionInput,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<SnapshotColumn>, HousecatError> {
+    let query = format!(
+        "SELECT table, name, type FROM system.columns WHERE database = {} AND table = {} ORDER BY position FORMAT JSON",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+
+    let rows: Vec<ClickHouseColumnRow> = fetch_rows(connection, query).await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| SnapshotColumn {
+            name: row.name,
+            data_type: row.data_type,
+        })
+        .collect())
+}
+
+/// The type of one column, or `None` when the table has no such column.
+pub(crate) async fn fetch_column_type(
+    connection: &ClickHouseConnectionInput,
+    schema: &str,
+    table: &str,
+    column: &str,
+) -> Result<Option<String>, HousecatError> {
+    Ok(fetch_table_columns(connection, schema, table)
+        .await?
+        .into_iter()
+        .find(|candidate| candidate.name == column)
+        .map(|candidate| candidate.data_type))
+}
+
+/// Differences between two column lists, reported from the point of view
+/// of `table`, the owner of `after`.
+pub(crate) fn diff_table(
+    table: &str,
+    before: &[SnapshotColumn],
+    after: &[SnapshotColumn],
+) -> TableDrift {
+    let added_columns = after
+        .iter()
+        .filter(|column| !before.iter().any(|old| old.name == column.name))
+        .cloned()
+        .collect();
+
+    let removed_columns = before
+        .iter()
+        .filter(|column| !after.iter().any(|new| new.name == column.name))
+        .cloned()
+        .collect();
+
+    let changed_columns = before
+        .iter()
+        .filter_map(|old| {
+            let new = after.iter().find(|new| new.name == old.name)?;
+            (new.data_type != old.data_type).then(|| ColumnTypeChange {
+                name: old.name.clone(),
+                snapshot_type: old.data_type.clone(),
+                current_type: new.data_type.clone(),
+            })
+        })
+        .collect();
+
+    TableDrift {
+        table: table.to_string(),
+        added_columns,
+        removed_columns,
+        changed_columns,
+    }
+}
+
+fn diff_snapshot(
+    snapshot: &BTreeMap<String, Vec<SnapshotColumn>>,
+    current: &BTreeMap<String, Vec<SnapshotColumn>>,
+) -> SchemaDrift {
+    let added_tables = current
+        .keys()
+        .filter(|table| !snapshot.contains_key(*table))
+        .cloned()
+        .collect();
+
+    let removed_tables = snapshot
+        .keys()
+        .filter(|table| !current.contains_key(*table))
+        .cloned()
+        .collect();
+
+    let changed_tables = snapshot
+        .iter()
+        .filter_map(|(table, before)| {
+            let after = current.get(table)?;
+            let drift = diff_table(table, before, after);
+            drift.has_changes().then_some(drift)
+        })
+        .collect();
+
+    SchemaDrift {
+        added_tables,
+        removed_tables,
+        changed_tables,
+    }
+}
+
+/// Captures every table and column of `database`. When `path` is given the
+/// snapshot is also written there as JSON so it can be compared later.
+#[tauri::command]
+pub(crate) async fn snapshot_schema(
+    input: SnapshotSchemaInput,
+) -> Result<SchemaSnapshot, HousecatError> {
+    let database = require_value(&input.database, "Database")?;
+    let tables = fetch_database_columns(&input.connection, database).await?;
+
+    let snapshot = SchemaSnapshot {
+        database: database.to_string(),
+        captured_at_unix: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0),
+        tables,
+    };
+
+    if let Some(path) = input.path.as_deref().map(str::trim) {
+        if !path.is_empty() {
+            let contents = serde_json::to_string_pretty(&snapshot)
+                .map_err(|err| HousecatError::Io(format!("Could not serialize snapshot: {err}")))?;
+            std::fs::write(path, contents)
+                .map_err(|err| HousecatError::Io(format!("Could not write {path}: {err}")))?;
+        }
+    }
+
+    Ok(snapshot)
+}
+
+#[tauri::command]
+pub(crate) async fn detect_schema_drift(
+    input: SchemaDriftInput,
+) -> Result<SchemaDrift, HousecatError> {
+    let database = require_value(&input.database, "Database")?;
+    let current = fetch_database_columns(&input.connection, database).await?;
+
+    Ok(diff_snapshot(&input.snapshot.tables, &current))
+}
+
+#[tauri::command]
+pub(crate) async fn table_exists(input: TableRefInput) -> Result<bool, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+
+    table_exists_in(&input.connection, schema, table).await
+}
+
+#[tauri::command]
+pub(crate) async fn fetch_columns_detailed(
+    input: TableRefInput,
+) -> Result<Vec<ColumnDetail>, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+
+    fetch_column_details(&input.connection, schema, table).await
+}
+
+async fn fetch_column_details(
+    connection: &ClickHouseConnectionInput,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<ColumnDetail>, HousecatError> {
+    let query = format!(
+        "SELECT position, name, type, default_kind, default_expression, comment, compression_codec FROM system.columns WHERE database = {} AND table = {} ORDER BY position FORMAT JSON",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+
+    fetch_rows(connection, query).await
+}
+
+#[tauri::command]
+pub(crate) async fn fetch_column_defaults(
+    input: TableRefInput,
+) -> Result<Vec<ColumnDefault>, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+
+    let query = format!(
+        "SELECT name, type, default_kind, default_expression FROM system.columns WHERE database = {} AND table = {} AND default_kind != '' ORDER BY position FORMAT JSON",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+
+    fetch_rows(&input.connection, query).await
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseTimeColumnRow {
+    name: String,
+    #[serde(rename = "type")]
+    data_type: String,
+    is_in_sorting_key: u8,
+}
+
+/// Column names that usually hold the time a row describes, best first.
+const TIME_COLUMN_NAMES: &[&str] = &[
+    "timestamp",
+    "event_time",
+    "time",
+    "ts",
+    "datetime",
+    "created_at",
+    "event_date",
+    "date",
+];
+
+/// Scores a `Date`/`DateTime` column as the table's time axis: being in the
+/// sorting key matters most, since filters on it prune parts, then a
+/// conventional name, then time-of-day precision over a bare date.
+fn time_column_score(column: &ClickHouseTimeColumnRow) -> Option<usize> {
+    if !base_type(&column.data_type).starts_with("Date") {
+        return None;
+    }
+
+    let name = column.name.to_lowercase();
+    let name_score = match TIME_COLUMN_NAMES.iter().position(|known| *known == name) {
+        Some(rank) => 2 * TIME_COLUMN_NAMES.len() - rank,
+        None if name.contains("time") || name.contains("date") => 1,
+        None => 0,
+    };
+    let sorting_score = if column.is_in_sorting_key != 0 {
+        4 * TIME_COLUMN_NAMES.len()
+    } else {
+        0
+    };
+    let precision_score = usize::from(base_type(&column.data_type).starts_with("DateTime"));
+
+    Some(sorting_score + name_score + precision_score)
+}
+
+/// Picks the column a time-range filter should default to, or `None` when
+/// the table has no `Date` or `DateTime` column. Ties go to the column
+/// defined first.
+#[tauri::command]
+pub(crate) async fn detect_time_column(
+    input: TableRefInput,
+) -> Result<Option<String>, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+
+    let query = format!(
+        "SELECT name, type, is_in_sorting_key FROM system.columns WHERE database = {} AND table = {} ORDER BY position FORMAT JSON",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+    let columns: Vec<ClickHouseTimeColumnRow> = fetch_rows(&input.connection, query).await?;
+
+    let mut best: Option<(usize, String)> = None;
+    for column in columns {
+        let Some(score) = time_column_score(&column) else {
+            continue;
+        };
+        if best
+            .as_ref()
+            .is_none_or(|(best_score, _)| score > *best_score)
+        {
+            best = Some((score, column.name));
+        }
+    }
+
+    Ok(best.map(|(_, name)| name))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TablesByEngineInput {
+    connection: ClickHouseConnectionInput,
+    database: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseTableEngineRow {
+    database: String,
+    name: String,
+    engine: String,
+}
+
+/// Engines that read from or write to an external system.
+const INTEGRATION_ENGINES: &[&str] = &[
+    "AzureBlobStorage",
+    "AzureQueue",
+    "DeltaLake",
+    "ExternalDistributed",
+    "HDFS",
+    "Hive",
+    "Hudi",
+    "JDBC",
+    "Kafka",
+    "MaterializedPostgreSQL",
+    "MongoDB",
+    "MySQL",
+    "NATS",
+    "ODBC",
+    "PostgreSQL",
+    "RabbitMQ",
+    "Redis",
+    "S3",
+    "S3Queue",
+    "SQLite",
+    "URL",
+];
+
+/// Groups an engine name into its family. Replicated and shared variants
+/// such as `ReplicatedReplacingMergeTree` count as MergeTree, and the
+/// Iceberg engines as integrations.
+fn engine_family(engine: &str) -> &'static str {
+    match engine {
+        engine if engine.ends_with("MergeTree") => "MergeTree",
+        "Log" | "TinyLog" | "StripeLog" => "Log",
+        "Distributed" => "Distributed",
+        "View" | "MaterializedView" | "LiveView" | "WindowView" => "View",
+        engine if INTEGRATION_ENGINES.contains(&engine) || engine.starts_with("Iceberg") => {
+            "Integration"
+        }
+        "Memory" | "Set" | "Join" | "Buffer" | "Null" | "Dictionary" | "Merge" | "File"
+        | "GenerateRandom" | "EmbeddedRocksDB" | "KeeperMap" => "Special",
+        _ => "Other",
+    }
+}
+
+/// Tables grouped by engine family, as `database.table` names. Without a
+/// database every user database is included.
+#[tauri::command]
+pub(crate) async fn fetch_tables_by_engine(
+    input: TablesByEngineInput,
+) -> Result<BTreeMap<String, Vec<String>>, HousecatError> {
+    let filter = match input.database.as_deref().map(str::trim) {
+        Some(database) if !database.is_empty() => {
+            format!("database = {}", quote_string_literal(database))
+        }
+        _ => "database NOT IN ('INFORMATION_SCHEMA', 'information_schema', 'system')".to_string(),
+    };
+    let query = format!(
+        "SELECT database, name, engine FROM system.tables WHERE {filter} ORDER BY database, name FORMAT JSON"
+    );
+
+    let rows: Vec<ClickHouseTableEngineRow> = fetch_rows(&input.connection, query).await?;
+    let mut families: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for row in rows {
+        families
+            .entry(engine_family(&row.engine).to_string())
+            .or_default()
+            .push(format!("{}.{}", row.database, row.name));
+    }
+
+    Ok(families)
+}
+
+/// Diffs the same table on two servers, such as staging and production.
+/// Added columns exist only on `connectionB`, removed ones only on
+/// `connectionA`.
+#[tauri::command]
+pub(crate) async fn compare_schemas_across(
+    input: CompareSchemasInput,
+) -> Result<TableDrift, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+
+    let columns_a = fetch_table_columns(&input.connection_a, schema, table).await?;
+    let columns_b = fetch_table_columns(&input.connection_b, schema, table).await?;
+
+    for (columns, label) in [(&columns_a, "A"), (&columns_b, "B")] {
+        if columns.is_empty() {
+            return Err(HousecatError::invalid_input(format!(
+                "Table {schema}.{table} does not exist on connection {label}"
+            )));
+        }
+    }
+
+    Ok(diff_table(table, &columns_a, &columns_b))
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseTableKeysRow {
+    engine: String,
+    sorting_key: String,
+    partition_key: String,
+    primary_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseCreateStatementRow {
+    statement: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHousePartTotalsRow {
+    active_parts: u64,
+    rows: u64,
+    compressed_bytes: u64,
+    uncompressed_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TableDetails {
+    columns: Vec<ColumnDetail>,
+    create_statement: String,
+    engine: String,
+    sorting_key: String,
+    partition_key: String,
+    primary_key: String,
+    /// Totals over active parts; zero for engines that store no parts.
+    active_parts: u64,
+    rows: u64,
+    compressed_bytes: u64,
+    uncompressed_bytes: u64,
+    detached_parts: u64,
+}
+
+/// Everything a table's structure tab shows: columns with their defaults
+/// and codecs, the `CREATE` statement, engine and keys, and part totals.
+#[tauri::command]
+pub(crate) async fn fetch_table_details(
+    input: TableRefInput,
+) -> Result<TableDetails, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+    let filter = format!(
+        "database = {} AND table = {}",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+
+    let keys_query = format!(
+        "SELECT engine, sorting_key, partition_key, primary_key FROM system.tables WHERE database = {} AND name = {} FORMAT JSON",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+    let keys = fetch_rows::<ClickHouseTableKeysRow>(&input.connection, keys_query)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            HousecatError::invalid_input(format!("Table {schema}.{table} does not exist"))
+        })?;
+
+    let create_statement = fetch_rows::<ClickHouseCreateStatementRow>(
+        &input.connection,
+        format!(
+            "SHOW CREATE TABLE {} FORMAT JSON",
+            qualified_table(schema, table)
+        ),
+    )
+    .await?
+    .into_iter()
+    .next()
+    .map(|row| row.statement)
+    .unwrap_or_default();
+
+    let parts_query = format!(
+        "SELECT count() AS active_parts, sum(rows) AS rows, sum(data_compressed_bytes) AS compressed_bytes, sum(data_uncompressed_bytes) AS uncompressed_bytes FROM system.parts WHERE active AND {filter} FORMAT JSON"
+    );
+    let parts = fetch_rows::<ClickHousePartTotalsRow>(&input.connection, parts_query)
+        .await?
+        .into_iter()
+        .next();
+
+    let detached_parts = fetch_count(
+        &input.connection,
+        format!("SELECT count() AS count FROM system.detached_parts WHERE {filter} FORMAT JSON"),
+    )
+    .await?;
+
+    Ok(TableDetails {
+        columns: fetch_column_details(&input.connection, schema, table).await?,
+        create_statement,
+        engine: keys.engine,
+        sorting_key: keys.sorting_key,
+        partition_key: keys.partition_key,
+        primary_key: keys.primary_key,
+        active_parts: parts.as_ref().map_or(0, |parts| parts.active_parts),
+        rows: parts.as_ref().map_or(0, |parts| parts.rows),
+        compressed_bytes: parts.as_ref().map_or(0, |parts| parts.compressed_bytes),
+        uncompressed_bytes: parts.as_ref().map_or(0, |parts| parts.uncompressed_bytes),
+        detached_parts,
+    })
+}
@@ -0,0 +1,80 @@
+use crate::error::HousecatError;
+use crate::{execute_recorded, QueryInput, QueryOutput};
+use serde::Serialize;
+use std::time::Instant;
+use tauri::AppHandle;
+
+/// What one statement of a script did.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StatementResult {
+    statement: String,
+    /// The rows it returned; `None` when it failed.
+    output: Option<QueryOutput>,
+    /// Rows inserted, as reported by the server's summary header.
+    written_rows: u64,
+    duration_ms: f64,
+    error: Option<HousecatError>,
+}
+
+impl StatementResult {
+    pub(crate) fn row_count(&self) -> usize {
+        self.output.as_ref().map_or(0, QueryOutput::row_count)
+    }
+}
+
+/// Runs `statements` one after another, each like a `run_query` of its
+/// own and each recorded in the history. Stops at the first failure, like
+/// `clickhouse-client --multiquery`, so later statements never run on top
+/// of a half-applied script.
+///
+/// All statements share one server session, so `SET`, `USE` and temporary
+/// tables carry over. A connection without a session gets a fresh one for
+/// the script. Each statement is its own query with its own id: the given
+/// `query_id` with `-1`, `-2`, ... appended, or a generated one.
+pub(crate) async fn run_script(
+    app: &AppHandle,
+    mut input: QueryInput,
+    statements: Vec<String>,
+) -> Vec<StatementResult> {
+    if input.connection.session_id.is_none() {
+        input.connection.session_id = Some(uuid::Uuid::new_v4().to_string());
+    }
+
+    let mut results = Vec::with_capacity(statements.len());
+    for (index, statement) in statements.into_iter().enumerate() {
+        let mut statement_input = input.clone();
+        statement_input.query = statement.clone();
+        statement_input.query_id = input
+            .query_id
+            .as_deref()
+            .map(|query_id| format!("{}-{}", query_id.trim(), index + 1));
+
+        let started = Instant::now();
+        let result = execute_recorded(app, statement_input).await;
+        let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+        let failed = result.is_err();
+        results.push(match result {
+            Ok((output, summary)) => StatementResult {
+                statement,
+                output: Some(output),
+                written_rows: summary.written_rows,
+                duration_ms,
+                error: None,
+            },
+            Err(err) => StatementResult {
+                statement,
+                output: None,
+                written_rows: 0,
+                duration_ms,
+                error: Some(err),
+            },
+        });
+        if failed {
+            break;
+        }
+    }
+
+    results
+}
@@ -0,0 +1,251 @@
+use crate::error::{HousecatError, SESSION_NOT_FOUND};
+use crate::{
+    clients, fetch_rows_with_params, require_value, run_clickhouse_query_with_params, tunnels,
+    ClickHouseConnectionInput,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+/// ClickHouse has no system table listing HTTP sessions, so the app keeps
+/// track of the sessions it opened itself.
+#[derive(Debug, Default)]
+pub(crate) struct SessionRegistry {
+    sessions: Mutex<BTreeMap<String, SessionInfo>>,
+}
+
+impl SessionRegistry {
+    pub(crate) fn list(&self) -> Vec<SessionInfo> {
+        self.sessions
+            .lock()
+            .map(|sessions| sessions.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The registered session opened for the connection with this
+    /// fingerprint, if any.
+    fn for_connection(&self, connection_id: &str) -> Option<SessionInfo> {
+        self.sessions.lock().ok().and_then(|sessions| {
+            sessions
+                .values()
+                .find(|session| session.connection_id == connection_id)
+                .cloned()
+        })
+    }
+
+    fn insert(&self, info: SessionInfo) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.insert(info.session_id.clone(), info);
+        }
+    }
+
+    fn remove(&self, session_id: &str) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.remove(session_id);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SessionInfo {
+    session_id: String,
+    /// The connection's [`clients::fingerprint`].
+    connection_id: String,
+    host: String,
+    port: u16,
+    username: String,
+    created_at_unix: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TemporaryTablesInput {
+    connection: ClickHouseConnectionInput,
+    session_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseTableName {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CloseSessionInput {
+    connection: ClickHouseConnectionInput,
+    session_id: String,
+}
+
+async fn start_session(
+    mut input: ClickHouseConnectionInput,
+    registry: &SessionRegistry,
+) -> Result<SessionInfo, HousecatError> {
+    let session_id = uuid::Uuid::new_v4().to_string();
+    input.session_id = Some(session_id.clone());
+
+    run_clickhouse_query_with_params(&input, "SELECT 1".to_string(), &[]).await?;
+
+    let info = SessionInfo {
+        session_id,
+        connection_id: clients::fingerprint(&input),
+        host: input.host.trim().to_string(),
+        port: input.port,
+        username: input.username.trim().to_string(),
+        created_at_unix: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0),
+    };
+    registry.insert(info.clone());
+
+    Ok(info)
+}
+
+/// Creates a server-side session and registers it. The returned id is passed
+/// back as `sessionId` on later connection inputs so `SET` statements and
+/// temporary tables persist between commands.
+#[tauri::command]
+pub(crate) async fn open_session(
+    input: ClickHouseConnectionInput,
+    registry: State<'_, SessionRegistry>,
+) -> Result<SessionInfo, HousecatError> {
+    start_session(input, &registry).await
+}
+
+/// Asks the server to end `session_id` by shortening its idle timeout to one
+/// second with a final request. Sessions that already expired count as
+/// closed.
+async fn expire_session(
+    mut connection: ClickHouseConnectionInput,
+    session_id: &str,
+) -> Result<(), HousecatError> {
+    connection.session_id = Some(session_id.to_string());
+
+    let result = run_clickhouse_query_with_params(
+        &connection,
+        "SELECT 1".to_string(),
+        &[
+            ("session_check", "1".to_string()),
+            ("session_timeout", "1".to_string()),
+        ],
+    )
+    .await;
+
+    match result {
+        Err(err) if err.clickhouse_code() != Some(SESSION_NOT_FOUND) => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub(crate) fn fetch_sessions(registry: State<'_, SessionRegistry>) -> Vec<SessionInfo> {
+    registry.list()
+}
+
+/// HTTP sessions cannot be closed explicitly, so this shortens the session's
+/// idle timeout to one second with a final request and forgets it locally.
+/// Sessions that already expired on the server are simply forgotten.
+#[tauri::command]
+pub(crate) async fn close_session(
+    input: CloseSessionInput,
+    registry: State<'_, SessionRegistry>,
+) -> Result<(), HousecatError> {
+    let session_id = require_value(&input.session_id, "Session id")?;
+
+    let result = expire_session(input.connection, session_id).await;
+    registry.remove(session_id);
+
+    result
+}
+
+/// Readies a connection for a browsing session: warms its pooled client and
+/// returns the server-side session registered for it, opening one when
+/// there is none or the old one expired. Passing the returned `sessionId`
+/// on later inputs keeps `SET` statements and temporary tables alive
+/// across `run_query` calls.
+#[tauri::command]
+pub(crate) async fn open_connection(
+    input: ClickHouseConnectionInput,
+    registry: State<'_, SessionRegistry>,
+) -> Result<SessionInfo, HousecatError> {
+    tunnels::ensure(&input).await?;
+    clients::client_for(&input)?;
+
+    if let Some(existing) = registry.for_connection(&clients::fingerprint(&input)) {
+        let mut connection = input.clone();
+        connection.session_id = Some(existing.session_id.clone());
+        let alive = run_clickhouse_query_with_params(
+            &connection,
+            "SELECT 1".to_string(),
+            &[("session_check", "1".to_string())],
+        )
+        .await;
+
+        match alive {
+            Ok(_) => return Ok(existing),
+            Err(err) if err.clickhouse_code() == Some(SESSION_NOT_FOUND) => {
+                registry.remove(&existing.session_id);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    start_session(input, &registry).await
+}
+
+/// Ends the connection's registered session and drops its pooled client,
+/// closing the kept-alive sockets, then shuts down its SSH tunnel.
+#[tauri::command]
+pub(crate) async fn close_connection(
+    input: ClickHouseConnectionInput,
+    registry: State<'_, SessionRegistry>,
+) -> Result<(), HousecatError> {
+    let result = match registry.for_connection(&clients::fingerprint(&input)) {
+        Some(session) => {
+            let result = expire_session(input.clone(), &session.session_id).await;
+            registry.remove(&session.session_id);
+            result
+        }
+        None => Ok(()),
+    };
+    clients::evict(&input);
+    tunnels::close(&input);
+
+    result
+}
+
+/// Lists the temporary tables of a session. They are only visible from
+/// inside their own session, so the lookup runs there with `session_check`
+/// set to avoid creating a fresh session when the old one has expired.
+#[tauri::command]
+pub(crate) async fn fetch_temporary_tables(
+    input: TemporaryTablesInput,
+) -> Result<Vec<String>, HousecatError> {
+    let Some(session_id) = input
+        .session_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+    else {
+        return Ok(Vec::new());
+    };
+
+    let mut connection = input.connection;
+    connection.session_id = Some(session_id.to_string());
+
+    let result = fetch_rows_with_params::<ClickHouseTableName>(
+        &connection,
+        "SELECT name FROM system.tables WHERE is_temporary ORDER BY name FORMAT JSON".to_string(),
+        &[("session_check", "1".to_string())],
+    )
+    .await;
+
+    match result {
+        Ok(rows) => Ok(rows.into_iter().map(|row| row.name).collect()),
+        Err(err) if err.clickhouse_code() == Some(SESSION_NOT_FOUND) => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
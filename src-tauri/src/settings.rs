@@ -0,0 +1,157 @@
+use crate::error::HousecatError;
+use std::collections::BTreeMap;
+
+/// URL parameters the backend sets itself, which per-query settings must not
+/// override.
+const RESERVED_PARAMETERS: &[&str] = &[
+    "query_id",
+    "session_id",
+    "session_check",
+    "session_timeout",
+    "database",
+    "default_format",
+    "user",
+    "password",
+    "query",
+    // Set from the connection's `readOnly` flag.
+    "readonly",
+];
+
+const DISTRIBUTED_PRODUCT_MODES: &[&str] = &["deny", "local", "global", "allow"];
+
+const JOIN_ALGORITHMS: &[&str] = &[
+    "default",
+    "auto",
+    "hash",
+    "parallel_hash",
+    "partial_merge",
+    "prefer_partial_merge",
+    "direct",
+    "full_sorting_merge",
+    "grace_hash",
+];
+
+fn invalid_value(name: &str, value: &str) -> HousecatError {
+    HousecatError::invalid_input(format!("Invalid value `{value}` for setting {name}"))
+}
+
+/// Checks the join-safety settings, which are easy to get subtly wrong.
+/// None of them is applied by default: `distributed_product_mode = 'deny'`
+/// and a `max_bytes_in_join` of a few GiB are sensible guards for
+/// Distributed joins, but both change results or fail queries that
+/// currently work, so they are left for the user to opt into.
+fn validate_join_setting(name: &str, value: &str) -> Result<(), HousecatError> {
+    let valid = match name {
+        "distributed_product_mode" => DISTRIBUTED_PRODUCT_MODES.contains(&value),
+        "join_algorithm" => value
+            .split(',')
+            .all(|algorithm| JOIN_ALGORITHMS.contains(&algorithm.trim())),
+        "max_bytes_in_join" | "max_rows_in_join" => value.parse::<u64>().is_ok(),
+        "join_overflow_mode" => matches!(value, "throw" | "break"),
+        _ => true,
+    };
+
+    if !valid {
+        return Err(invalid_value(name, value));
+    }
+
+    Ok(())
+}
+
+/// Validates per-query settings and turns them into URL parameters.
+pub(crate) fn settings_params(
+    settings: Option<&BTreeMap<String, String>>,
+) -> Result<Vec<(&str, String)>, HousecatError> {
+    let Some(settings) = settings else {
+        return Ok(Vec::new());
+    };
+
+    settings
+        .iter()
+        .map(|(name, value)| {
+            let name = name.trim();
+            let value = value.trim();
+
+            let well_formed =
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !well_formed || name.starts_with("param_") || RESERVED_PARAMETERS.contains(&name) {
+                return Err(HousecatError::invalid_input(format!(
+                    "`{name}` cannot be passed as a query setting"
+                )));
+            }
+
+            validate_join_setting(name, value)?;
+            Ok((name, value.to_string()))
+        })
+        .collect()
+}
+
+/// Turns query parameters into `param_<name>` URL parameters, which
+/// ClickHouse substitutes for `{name:Type}` placeholders server-side, so
+/// values never have to be escaped into the SQL text. Values are sent as
+/// given, untrimmed.
+pub(crate) fn query_parameter_params(
+    parameters: Option<&BTreeMap<String, String>>,
+) -> Result<Vec<(String, String)>, HousecatError> {
+    let Some(parameters) = parameters else {
+        return Ok(Vec::new());
+    };
+
+    parameters
+        .iter()
+        .map(|(name, value)| {
+            let well_formed = name
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !well_formed {
+                return Err(HousecatError::invalid_input(format!(
+                    "`{name}` is not a valid query parameter name"
+                )));
+            }
+
+            Ok((format!("param_{name}"), value.clone()))
+        })
+        .collect()
+}
+
+/// Longest `log_comment` the app will send; tags are for grouping queries,
+/// not for carrying payloads.
+const MAX_LOG_COMMENT_LEN: usize = 1024;
+
+/// Serializes app-level tags (user, dashboard, panel, ...) into the
+/// `log_comment` setting as a JSON object so they can be filtered on in
+/// `system.query_log`.
+pub(crate) fn tags_log_comment(
+    tags: Option<&BTreeMap<String, String>>,
+) -> Result<Option<(&'static str, String)>, HousecatError> {
+    let Some(tags) = tags.filter(|tags| !tags.is_empty()) else {
+        return Ok(None);
+    };
+
+    for key in tags.keys() {
+        let well_formed = !key.is_empty()
+            && key.len() <= 64
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if !well_formed {
+            return Err(HousecatError::invalid_input(format!(
+                "Tag key `{key}` must be 1-64 letters, digits, `_`, `-` or `.`"
+            )));
+        }
+    }
+
+    let comment = serde_json::to_string(tags)
+        .map_err(|err| HousecatError::invalid_input(format!("Could not serialize tags: {err}")))?;
+
+    if comment.len() > MAX_LOG_COMMENT_LEN {
+        return Err(HousecatError::invalid_input(format!(
+            "Tags serialize to {} bytes, above the {MAX_LOG_COMMENT_LEN} byte limit",
+            comment.len()
+        )));
+    }
+
+    Ok(Some(("log_comment", comment)))
+}
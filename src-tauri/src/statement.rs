@@ -0,0 +1,560 @@
+use crate::error::HousecatError;
+use crate::ClickHouseConnectionInput;
+use std::collections::BTreeSet;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+/// Broad class of a SQL statement, judged by its leading keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StatementKind {
+    /// `SELECT`, `SHOW`, `DESCRIBE`, `EXPLAIN`, `EXISTS`, and `WITH` unless
+    /// it leads into a write.
+    Read,
+    /// `SET` and `USE`, which only change session state.
+    Session,
+    /// `INSERT`, `DELETE` and `UPDATE`, with or without a `WITH` clause.
+    Write,
+    /// Schema changes such as `CREATE`, `ALTER`, `DROP` or `TRUNCATE`.
+    Ddl,
+    /// Server administration such as `KILL`, `SYSTEM` or `GRANT`.
+    Admin,
+    /// Anything unrecognized, which callers should treat as mutating.
+    Unknown,
+}
+
+impl StatementKind {
+    pub(crate) fn is_read_only(self) -> bool {
+        self == Self::Read
+    }
+}
+
+/// Skips leading whitespace, `--`/`#` line comments, `/* */` block comments
+/// and opening parentheses so the first keyword can be inspected.
+fn skip_preamble(mut sql: &str) -> &str {
+    loop {
+        let trimmed = sql.trim_start().trim_start_matches('(');
+        if trimmed.len() != sql.len() {
+            sql = trimmed;
+            continue;
+        }
+
+        if sql.starts_with("--") || sql.starts_with('#') {
+            sql = sql.find('\n').map_or("", |end| &sql[end + 1..]);
+        } else if let Some(rest) = sql.strip_prefix("/*") {
+            sql = rest.find("*/").map_or("", |end| &rest[end + 2..]);
+        } else {
+            return sql;
+        }
+    }
+}
+
+/// The first keyword of `sql`, uppercased, after comments are skipped.
+pub(crate) fn leading_keyword(sql: &str) -> String {
+    skip_preamble(sql)
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+pub(crate) fn classify_statement(sql: &str) -> StatementKind {
+    match leading_keyword(sql).as_str() {
+        "WITH" if writes_after_with_clause(sql) => StatementKind::Write,
+        "SELECT" | "WITH" | "SHOW" | "DESCRIBE" | "DESC" | "EXPLAIN" | "EXISTS" => {
+            StatementKind::Read
+        }
+        "SET" | "USE" => StatementKind::Session,
+        "INSERT" | "DELETE" | "UPDATE" => StatementKind::Write,
+        "CREATE" | "ALTER" | "DROP" | "TRUNCATE" | "RENAME" | "ATTACH" | "DETACH" | "OPTIMIZE"
+        | "EXCHANGE" | "UNDROP" => StatementKind::Ddl,
+        "KILL" | "SYSTEM" | "GRANT" | "REVOKE" => StatementKind::Admin,
+        _ => StatementKind::Unknown,
+    }
+}
+
+/// Whether a `WITH` clause is followed by `INSERT`, `DELETE` or `UPDATE`
+/// rather than `SELECT`. Keywords inside the parenthesized expressions
+/// being named are not counted.
+fn writes_after_with_clause(sql: &str) -> bool {
+    let mut depth = 0usize;
+    for token in tokens(sql) {
+        match token.as_str() {
+            "(" => depth += 1,
+            ")" => depth = depth.saturating_sub(1),
+            "INSERT" | "DELETE" | "UPDATE" if depth == 0 => return true,
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// Splits a script into its statements at each `;` outside string
+/// literals, quoted identifiers and comments. The statements are trimmed
+/// and lose their `;`; empty ones, or ones holding only comments, are
+/// dropped.
+pub(crate) fn split_statements(sql: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut chars = sql.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        if skip_literal_or_comment(c, &mut chars) {
+            continue;
+        }
+        if c == ';' {
+            statements.push(&sql[start..index]);
+            start = index + 1;
+        }
+    }
+    statements.push(&sql[start..]);
+
+    statements
+        .into_iter()
+        .map(str::trim)
+        .filter(|statement| !skip_preamble(statement).is_empty())
+        .collect()
+}
+
+/// Consumes the rest of the string literal, quoted identifier or comment
+/// that `c` opens, returning whether it opened one.
+fn skip_literal_or_comment(c: char, chars: &mut Peekable<CharIndices<'_>>) -> bool {
+    match c {
+        '\'' | '"' | '`' => {
+            while let Some((_, next)) = chars.next() {
+                if next == '\\' {
+                    chars.next();
+                } else if next == c {
+                    break;
+                }
+            }
+        }
+        '-' if chars.peek().map(|&(_, next)| next) == Some('-') => {
+            while chars.next_if(|&(_, next)| next != '\n').is_some() {}
+        }
+        '#' => while chars.next_if(|&(_, next)| next != '\n').is_some() {},
+        '/' if chars.peek().map(|&(_, next)| next) == Some('*') => {
+            chars.next();
+            let mut previous = ' ';
+            for (_, next) in chars.by_ref() {
+                if previous == '*' && next == '/' {
+                    break;
+                }
+                previous = next;
+            }
+        }
+        _ => return false,
+    }
+
+    true
+}
+
+/// Names of the `{name:Type}` query parameter placeholders in `sql`,
+/// outside string literals and comments, sorted and deduplicated.
+pub(crate) fn query_placeholders(sql: &str) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let mut chars = sql.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        if skip_literal_or_comment(c, &mut chars) || c != '{' {
+            continue;
+        }
+
+        let rest = &sql[index + 1..];
+        let Some((name, data_type)) = rest.find('}').and_then(|end| rest[..end].split_once(':'))
+        else {
+            continue;
+        };
+        let name = name.trim();
+        let well_formed = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if well_formed && !data_type.trim().is_empty() {
+            names.insert(name.to_string());
+        }
+    }
+
+    names
+}
+
+/// Trims whitespace and a trailing `;`, rejecting an empty statement.
+pub(crate) fn clean_statement(sql: &str) -> Result<&str, HousecatError> {
+    let cleaned = sql.trim().trim_end_matches(';').trim();
+    if cleaned.is_empty() {
+        return Err(HousecatError::invalid_input("Query is required"));
+    }
+
+    Ok(cleaned)
+}
+
+/// Cleans `sql` and rejects anything but a read-only statement, for commands
+/// that wrap the user's query in `EXPLAIN` or run it more than once.
+pub(crate) fn require_read_only(sql: &str) -> Result<&str, HousecatError> {
+    let cleaned = clean_statement(sql)?;
+    if !classify_statement(cleaned).is_read_only() {
+        return Err(HousecatError::invalid_input(
+            "Only read-only statements such as SELECT are allowed here",
+        ));
+    }
+
+    Ok(cleaned)
+}
+
+/// Rejects statements that could change data or schema on a connection
+/// marked read-only, before they are sent. `SET` and `USE` stay allowed
+/// since they only affect the session.
+pub(crate) fn require_allowed_on_connection(
+    sql: &str,
+    connection: &ClickHouseConnectionInput,
+) -> Result<(), HousecatError> {
+    if !connection.read_only.unwrap_or(false) {
+        return Ok(());
+    }
+
+    match classify_statement(sql) {
+        StatementKind::Read | StatementKind::Session => Ok(()),
+        _ => Err(HousecatError::ReadOnly(format!(
+            "{} statements are not allowed",
+            leading_keyword(sql)
+        ))),
+    }
+}
+
+/// Checks a user-supplied `WHERE` condition before it is spliced into a
+/// statement: it must be non-empty, keep quotes and parentheses balanced and
+/// contain no `;` or comment outside a string literal, so it cannot close
+/// the surrounding parentheses or hide the rest of the statement.
+pub(crate) fn validate_where_clause(clause: &str) -> Result<&str, HousecatError> {
+    validate_expression(clause, "WHERE condition")
+}
+
+/// The checks of [`validate_where_clause`] for any SQL expression spliced
+/// into a statement, such as a column default. `label` names it in errors.
+pub(crate) fn validate_expression<'a>(
+    clause: &'a str,
+    label: &str,
+) -> Result<&'a str, HousecatError> {
+    let clause = clause.trim();
+    if clause.is_empty() {
+        return Err(HousecatError::invalid_input(format!(
+            "A {label} is required"
+        )));
+    }
+
+    let invalid = |reason: &str| {
+        Err(HousecatError::invalid_input(format!(
+            "Invalid {label}: {reason}"
+        )))
+    };
+
+    let mut quote: Option<char> = None;
+    let mut depth = 0usize;
+    let mut chars = clause.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if let Some(open) = quote {
+            if c == '\\' {
+                chars.next();
+            } else if c == open {
+                quote = None;
+            }
+            continue;
+        }
+
+        match c {
+            '\'' | '"' | '`' => quote = Some(c),
+            '(' => depth += 1,
+            ')' if depth == 0 => return invalid("unbalanced parentheses"),
+            ')' => depth -= 1,
+            ';' => return invalid("multiple statements are not allowed"),
+            '-' if chars.peek() == Some(&'-') => return invalid("comments are not allowed"),
+            '/' if chars.peek() == Some(&'*') => return invalid("comments are not allowed"),
+            '#' => return invalid("comments are not allowed"),
+            _ => {}
+        }
+    }
+
+    if quote.is_some() {
+        return invalid("unterminated quote");
+    }
+
+    if depth != 0 {
+        return invalid("unbalanced parentheses");
+    }
+
+    Ok(clause)
+}
+
+/// Functions whose result changes between executions of the same query.
+const NON_DETERMINISTIC_FUNCTIONS: &[&str] = &[
+    "now",
+    "now64",
+    "nowinblock",
+    "today",
+    "yesterday",
+    "rand",
+    "rand32",
+    "rand64",
+    "randconstant",
+    "randuniform",
+    "randnormal",
+    "generateuuidv4",
+    "generateuuidv7",
+    "rownumberinallblocks",
+    "rownumberinblock",
+    "blocknumber",
+];
+
+/// Lowercased names of every identifier directly followed by `(`, skipping
+/// string literals and quoted identifiers.
+fn function_calls(sql: &str) -> Vec<String> {
+    let mut calls = Vec::new();
+    let mut chars = sql.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if matches!(c, '\'' | '"' | '`') {
+            while let Some((_, next)) = chars.next() {
+                if next == '\\' {
+                    chars.next();
+                } else if next == c {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if !(c.is_ascii_alphabetic() || c == '_') {
+            continue;
+        }
+
+        let mut end = start + c.len_utf8();
+        while let Some(&(index, next)) = chars.peek() {
+            if !(next.is_ascii_alphanumeric() || next == '_') {
+                break;
+            }
+            end = index + next.len_utf8();
+            chars.next();
+        }
+
+        if sql[end..].trim_start().starts_with('(') {
+            calls.push(sql[start..end].to_ascii_lowercase());
+        }
+    }
+
+    calls
+}
+
+/// Non-deterministic functions called by `sql`, deduplicated.
+pub(crate) fn non_deterministic_functions(sql: &str) -> Vec<String> {
+    let mut found: Vec<String> = function_calls(sql)
+        .into_iter()
+        .filter(|name| NON_DETERMINISTIC_FUNCTIONS.contains(&name.as_str()))
+        .collect();
+    found.sort();
+    found.dedup();
+    found
+}
+
+/// Splits `sql` into words and single punctuation characters, skipping
+/// comments and string literals. Words are uppercased unless they were
+/// quoted identifiers, which keep their case without the quotes.
+pub(crate) fn tokens(sql: &str) -> Vec<String> {
+    positioned_tokens(sql)
+        .into_iter()
+        .map(|(_, token)| token)
+        .collect()
+}
+
+/// [`tokens`] along with the byte offset in `sql` where each one starts.
+pub(crate) fn positioned_tokens(sql: &str) -> Vec<(usize, String)> {
+    let mut tokens = Vec::new();
+    let mut chars = sql.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '\'' => {
+                while let Some((_, next)) = chars.next() {
+                    if next == '\\' {
+                        chars.next();
+                    } else if next == '\'' {
+                        break;
+                    }
+                }
+            }
+            '"' | '`' => {
+                let mut identifier = String::new();
+                while let Some((_, next)) = chars.next() {
+                    if next == '\\' {
+                        if let Some((_, escaped)) = chars.next() {
+                            identifier.push(escaped);
+                        }
+                    } else if next == c {
+                        break;
+                    } else {
+                        identifier.push(next);
+                    }
+                }
+                tokens.push((start, identifier));
+            }
+            '-' if chars.peek().map(|&(_, next)| next) == Some('-') => {
+                while chars.next_if(|&(_, next)| next != '\n').is_some() {}
+            }
+            '#' => while chars.next_if(|&(_, next)| next != '\n').is_some() {},
+            '/' if chars.peek().map(|&(_, next)| next) == Some('*') => {
+                chars.next();
+                let mut previous = ' ';
+                for (_, next) in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some((index, next)) =
+                    chars.next_if(|&(_, next)| next.is_ascii_alphanumeric() || next == '_')
+                {
+                    end = index + next.len_utf8();
+                }
+                tokens.push((start, sql[start..end].to_ascii_uppercase()));
+            }
+            c if c.is_whitespace() => {}
+            c => tokens.push((start, c.to_string())),
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_at_semicolons() {
+        assert_eq!(
+            split_statements("SELECT 1;\nSELECT 2 ;"),
+            vec!["SELECT 1", "SELECT 2"]
+        );
+    }
+
+    #[test]
+    fn keeps_semicolons_inside_quotes() {
+        assert_eq!(
+            split_statements(r#"SELECT ';', "a;b" FROM `t;u`; SELECT 2"#),
+            vec![r#"SELECT ';', "a;b" FROM `t;u`"#, "SELECT 2"]
+        );
+    }
+
+    #[test]
+    fn keeps_escaped_quotes_inside_literals() {
+        assert_eq!(
+            split_statements(r"SELECT 'it\';s', `a\`;b`; SELECT 2"),
+            vec![r"SELECT 'it\';s', `a\`;b`", "SELECT 2"]
+        );
+    }
+
+    #[test]
+    fn keeps_semicolons_inside_comments() {
+        assert_eq!(
+            split_statements("SELECT 1 -- a;b\n; SELECT 2 # c;d\n; SELECT /* e;f */ 3"),
+            vec!["SELECT 1 -- a;b", "SELECT 2 # c;d", "SELECT /* e;f */ 3"]
+        );
+    }
+
+    #[test]
+    fn drops_comment_only_statements() {
+        assert_eq!(split_statements("SELECT 1; -- done"), vec!["SELECT 1"]);
+        assert_eq!(
+            split_statements("SELECT 1; /* a */ ; # b"),
+            vec!["SELECT 1"]
+        );
+        assert!(split_statements(" ; ;\n").is_empty());
+    }
+
+    fn connection(read_only: bool) -> ClickHouseConnectionInput {
+        serde_json::from_value(serde_json::json!({
+            "host": "localhost",
+            "port": 8123,
+            "username": "default",
+            "password": "",
+            "secure": false,
+            "readOnly": read_only,
+        }))
+        .expect("connection input")
+    }
+
+    #[test]
+    fn classifies_past_leading_comments_and_parentheses() {
+        assert_eq!(
+            classify_statement("/* x */ (SELECT 1) UNION ALL (SELECT 2)"),
+            StatementKind::Read
+        );
+        assert_eq!(classify_statement("-- c\nDROP TABLE t"), StatementKind::Ddl);
+        assert_eq!(
+            classify_statement("# c\n\t((select 1))"),
+            StatementKind::Read
+        );
+    }
+
+    #[test]
+    fn classifies_with_clause_by_the_statement_it_leads_into() {
+        assert_eq!(
+            classify_statement("WITH 1 AS x SELECT x"),
+            StatementKind::Read
+        );
+        assert_eq!(
+            classify_statement("WITH (SELECT max(id) FROM t) AS m INSERT INTO u SELECT m"),
+            StatementKind::Write
+        );
+        assert_eq!(
+            classify_statement("WITH x AS (SELECT 1 AS a) SELECT 'INSERT' FROM x"),
+            StatementKind::Read
+        );
+    }
+
+    #[test]
+    fn classifies_set_and_use_as_session() {
+        assert_eq!(
+            classify_statement("SET max_threads = 1"),
+            StatementKind::Session
+        );
+        assert_eq!(classify_statement("use analytics"), StatementKind::Session);
+    }
+
+    #[test]
+    fn read_only_connections_allow_only_reads_and_session_changes() {
+        let read_only = connection(true);
+        assert!(require_allowed_on_connection("/* x */ (SELECT 1)", &read_only).is_ok());
+        assert!(require_allowed_on_connection("SET max_threads = 1", &read_only).is_ok());
+        assert!(require_allowed_on_connection("USE analytics", &read_only).is_ok());
+        assert!(require_allowed_on_connection("-- c\nDROP TABLE t", &read_only).is_err());
+        assert!(
+            require_allowed_on_connection("WITH 1 AS x INSERT INTO t SELECT x", &read_only)
+                .is_err()
+        );
+
+        assert!(require_allowed_on_connection("DROP TABLE t", &connection(false)).is_ok());
+    }
+
+    #[test]
+    fn treats_unknown_keywords_as_mutating() {
+        assert_eq!(classify_statement("UPSERT t"), StatementKind::Unknown);
+        assert!(!StatementKind::Unknown.is_read_only());
+        assert!(require_allowed_on_connection("UPSERT t", &connection(true)).is_err());
+        assert!(require_read_only("BACKUP TABLE t TO Disk('b', 'x')").is_err());
+    }
+
+    #[test]
+    fn finds_placeholders_outside_literals_and_comments() {
+        let sql = "SELECT {id:UInt64}, '{quoted:String}', {id: UInt64} -- {note:String}\n\
+            FROM t WHERE x IN {ids:Array(UInt8)} AND y = {bad name:String} /* {skip:Int8} */";
+        assert_eq!(
+            query_placeholders(sql).into_iter().collect::<Vec<_>>(),
+            vec!["id", "ids"]
+        );
+    }
+}
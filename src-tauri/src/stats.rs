@@ -0,0 +1,250 @@
+use crate::error::HousecatError;
+use crate::{
+    escape_identifier, fetch_query_preview, fetch_rows, qualified_table, quote_string_literal,
+    require_value, ClickHouseConnectionInput, TablePreview,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Standard error of `uniqCombined` at its default precision (2^17 HLL
+/// cells), in percent. Small cardinalities are counted exactly.
+const UNIQ_COMBINED_ERROR_PERCENT: f64 = 0.29;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ApproxDistinctInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+    column: String,
+    exact: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseDistinctRow {
+    distinct_count: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ApproxDistinct {
+    estimate: u64,
+    exact: bool,
+    /// `None` for exact counts.
+    typical_error_percent: Option<f64>,
+}
+
+/// Counts distinct values of a column with `uniqCombined`, or `uniqExact`
+/// when `exact` is set, which is precise but needs memory proportional to
+/// the cardinality.
+#[tauri::command]
+pub(crate) async fn approx_distinct(
+    input: ApproxDistinctInput,
+) -> Result<ApproxDistinct, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+    let column = require_value(&input.column, "Column")?;
+    let exact = input.exact.unwrap_or(false);
+    let function = if exact { "uniqExact" } else { "uniqCombined" };
+
+    let query = format!(
+        "SELECT {function}(`{}`) AS distinct_count FROM {} FORMAT JSON",
+        escape_identifier(column),
+        qualified_table(schema, table)
+    );
+
+    let rows: Vec<ClickHouseDistinctRow> = fetch_rows(&input.connection, query).await?;
+
+    Ok(ApproxDistinct {
+        estimate: rows.first().map_or(0, |row| row.distinct_count),
+        exact,
+        typical_error_percent: (!exact).then_some(UNIQ_COMBINED_ERROR_PERCENT),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct NullRatesInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+    columns: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseColumnTypeRow {
+    name: String,
+    #[serde(rename = "type")]
+    data_type: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct NullRate {
+    column: String,
+    nullable: bool,
+    /// `None` for columns that cannot hold NULL, which were not counted.
+    null_count: Option<u64>,
+    null_rate: Option<f64>,
+    note: Option<String>,
+}
+
+fn is_nullable_type(data_type: &str) -> bool {
+    let data_type = data_type.trim();
+    let data_type = data_type
+        .strip_prefix("LowCardinality(")
+        .unwrap_or(data_type);
+    data_type.starts_with("Nullable(")
+}
+
+/// Counts NULLs in each requested column with one scan of the table.
+/// Columns that are not `Nullable` are reported without being counted.
+#[tauri::command]
+pub(crate) async fn fetch_null_rates(
+    input: NullRatesInput,
+) -> Result<Vec<NullRate>, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+    let columns = input
+        .columns
+        .iter()
+        .map(|column| require_value(column, "Column"))
+        .collect::<Result<Vec<_>, _>>()?;
+    if columns.is_empty() {
+        return Err(HousecatError::invalid_input(
+            "At least one column is required",
+        ));
+    }
+
+    let types_query = format!(
+        "SELECT name, type FROM system.columns WHERE database = {} AND table = {} FORMAT JSON",
+        quote_string_literal(schema),
+        quote_string_literal(table)
+    );
+    let types: BTreeMap<String, String> =
+        fetch_rows::<ClickHouseColumnTypeRow>(&input.connection, types_query)
+            .await?
+            .into_iter()
+            .map(|row| (row.name, row.data_type))
+            .collect();
+
+    let mut nullable = Vec::with_capacity(columns.len());
+    for column in &columns {
+        let data_type = types.get(*column).ok_or_else(|| {
+            HousecatError::invalid_input(format!(
+                "Column `{column}` does not exist in {schema}.{table}"
+            ))
+        })?;
+        nullable.push(is_nullable_type(data_type));
+    }
+
+    let mut select_list = vec!["count() AS total".to_string()];
+    for (index, column) in columns.iter().enumerate() {
+        if nullable[index] {
+            select_list.push(format!(
+                "countIf(`{}` IS NULL) AS null_{index}",
+                escape_identifier(column)
+            ));
+        }
+    }
+
+    let mut counts = BTreeMap::new();
+    if nullable.contains(&true) {
+        let query = format!(
+            "SELECT {} FROM {} FORMAT JSON",
+            select_list.join(", "),
+            qualified_table(schema, table)
+        );
+        let rows: Vec<BTreeMap<String, Value>> = fetch_rows(&input.connection, query).await?;
+        counts = rows.into_iter().next().unwrap_or_default();
+    }
+    let total = counts.get("total").and_then(Value::as_u64).unwrap_or(0);
+
+    Ok(columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            if !nullable[index] {
+                return NullRate {
+                    column: column.to_string(),
+                    nullable: false,
+                    null_count: None,
+                    null_rate: None,
+                    note: Some("Column is not Nullable, so it cannot contain NULL".to_string()),
+                };
+            }
+
+            let null_count = counts
+                .get(&format!("null_{index}"))
+                .and_then(Value::as_u64)
+                .unwrap_or(0);
+            NullRate {
+                column: column.to_string(),
+                nullable: true,
+                null_count: Some(null_count),
+                null_rate: Some(if total == 0 {
+                    0.0
+                } else {
+                    null_count as f64 / total as f64
+                }),
+                note: None,
+            }
+        })
+        .collect())
+}
+
+/// Aggregates `rolling_aggregate` may apply over its window.
+const ROLLING_AGGREGATES: &[&str] = &["avg", "sum", "min", "max", "count", "median"];
+
+/// Longest window, in rows, that `rolling_aggregate` accepts.
+const MAX_ROLLING_WINDOW: u32 = 10_000;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RollingAggregateInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+    time_column: String,
+    value_column: String,
+    /// Rows per window, the current one included.
+    window: u32,
+    agg: String,
+    limit: Option<u32>,
+}
+
+/// Smooths `value_column` with a rolling `agg` over the previous `window`
+/// rows in `time_column` order, as a window function, and returns the
+/// latest `limit` rows oldest first. The aggregate is computed over the
+/// whole table, so the first rows shown already have full windows.
+#[tauri::command]
+pub(crate) async fn rolling_aggregate(
+    input: RollingAggregateInput,
+) -> Result<TablePreview, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?;
+    let table = require_value(&input.table, "Table")?;
+    let time_column = escape_identifier(require_value(&input.time_column, "Time column")?);
+    let value_column = escape_identifier(require_value(&input.value_column, "Value column")?);
+    let agg = input.agg.trim();
+    if !ROLLING_AGGREGATES.contains(&agg) {
+        return Err(HousecatError::invalid_input(format!(
+            "Unsupported aggregate `{agg}`; expected one of {}",
+            ROLLING_AGGREGATES.join(", ")
+        )));
+    }
+    if !(1..=MAX_ROLLING_WINDOW).contains(&input.window) {
+        return Err(HousecatError::invalid_input(format!(
+            "Window must be between 1 and {MAX_ROLLING_WINDOW} rows"
+        )));
+    }
+    let limit = input.limit.unwrap_or(500).clamp(1, 10_000);
+
+    let query = format!(
+        "SELECT * FROM (SELECT `{time_column}`, `{value_column}`, {agg}(`{value_column}`) OVER (ORDER BY `{time_column}` ROWS BETWEEN {} PRECEDING AND CURRENT ROW) AS rolling_{agg} FROM {} ORDER BY `{time_column}` DESC LIMIT {limit}) ORDER BY `{time_column}`",
+        input.window - 1,
+        qualified_table(schema, table)
+    );
+
+    fetch_query_preview(&input.connection, &query, limit).await
+}
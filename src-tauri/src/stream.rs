@@ -0,0 +1,250 @@
+use crate::error::HousecatError;
+use crate::{
+    prepare_query, progress, query_input_params, require_value, run_clickhouse_query_with_params,
+    settings, statement, ColumnInfo, QueryInput,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::future::Future;
+use tauri::ipc::Channel;
+use tauri::AppHandle;
+use tokio::time::Instant;
+
+/// Rows per chunk sent to the frontend, unless `batchRows` says otherwise.
+const STREAM_BATCH_ROWS: u32 = 500;
+
+/// Largest `batchRows`, which bounds how many decoded rows are held before
+/// being handed to the frontend.
+const MAX_STREAM_BATCH_ROWS: u32 = 10_000;
+
+/// One message of a streamed query. The first carries the columns; the last
+/// has `done` set, and carries `error` when the query failed or
+/// `deadline_exceeded` when it was cut short by `deadlineMs`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueryChunk {
+    columns: Option<Vec<ColumnInfo>>,
+    rows: Vec<Value>,
+    done: bool,
+    deadline_exceeded: bool,
+    error: Option<HousecatError>,
+}
+
+/// Decodes `JSONCompactEachRowWithNamesAndTypes`: a line of column names,
+/// a line of types, then one JSON array per row, turned into objects keyed
+/// by column name like the rows of a `TablePreview`.
+struct RowDecoder {
+    names: Option<Vec<String>>,
+    columns: Option<Vec<ColumnInfo>>,
+}
+
+impl RowDecoder {
+    fn parse_strings(line: &[u8]) -> Result<Vec<String>, HousecatError> {
+        serde_json::from_slice(line).map_err(|err| HousecatError::Parse(err.to_string()))
+    }
+
+    /// Consumes one line, returning a row once the header has been read.
+    fn decode(&mut self, line: &[u8]) -> Result<Option<Value>, HousecatError> {
+        let Some(names) = &self.names else {
+            self.names = Some(Self::parse_strings(line)?);
+            return Ok(None);
+        };
+
+        if self.columns.is_none() {
+            let types = Self::parse_strings(line)?;
+            self.columns = Some(
+                names
+                    .iter()
+                    .zip(types)
+                    .map(|(name, data_type)| ColumnInfo {
+                        name: name.clone(),
+                        data_type,
+                    })
+                    .collect(),
+            );
+            return Ok(None);
+        }
+
+        // ClickHouse reports errors that happen mid-stream as plain text in
+        // the body, which fails to parse here and ends the stream.
+        let values: Vec<Value> = serde_json::from_slice(line).map_err(|_| {
+            HousecatError::Response(String::from_utf8_lossy(line).trim().to_string())
+        })?;
+        let row: Map<String, Value> = names.iter().cloned().zip(values).collect();
+        Ok(Some(Value::Object(row)))
+    }
+}
+
+struct ChunkSender<'a> {
+    channel: &'a Channel<QueryChunk>,
+    columns_sent: bool,
+    deadline_exceeded: bool,
+    batch_rows: usize,
+    rows: Vec<Value>,
+}
+
+impl ChunkSender<'_> {
+    fn send(
+        &mut self,
+        columns: Option<&Vec<ColumnInfo>>,
+        done: bool,
+        error: Option<HousecatError>,
+    ) -> Result<(), HousecatError> {
+        let columns = if self.columns_sent {
+            None
+        } else {
+            self.columns_sent = columns.is_some();
+            columns.cloned()
+        };
+
+        self.channel
+            .send(QueryChunk {
+                columns,
+                rows: std::mem::take(&mut self.rows),
+                done,
+                deadline_exceeded: self.deadline_exceeded,
+                error,
+            })
+            .map_err(|err| HousecatError::Io(format!("Could not send query rows: {err}")))
+    }
+}
+
+/// Awaits `future` until `deadline`, or returns `None` if it passes first.
+async fn within<F: Future>(deadline: Option<Instant>, future: F) -> Option<F::Output> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+/// Sends the query's rows, returning whether the deadline stopped it. Rows
+/// received before the deadline have been sent either way.
+async fn stream_rows(
+    input: &QueryInput,
+    query_id: &str,
+    decoder: &mut RowDecoder,
+    sender: &mut ChunkSender<'_>,
+) -> Result<bool, HousecatError> {
+    statement::require_allowed_on_connection(&input.query, &input.connection)?;
+    // Only one batch is held at a time, so the result is not capped like
+    // `run_query`'s unless the caller asks for a limit.
+    let limit = input.limit.map(|limit| limit.max(1));
+    let query = prepare_query(&input.query, limit, "JSONCompactEachRowWithNamesAndTypes")?;
+
+    let query_parameters = settings::query_parameter_params(input.parameters.as_ref())?;
+    let mut params = query_input_params(input, &query_parameters)?;
+    params.push(("query_id", query_id.to_string()));
+
+    let deadline = input.deadline().map(|deadline| Instant::now() + deadline);
+    let request = run_clickhouse_query_with_params(&input.connection, query, &params);
+    let Some(response) = within(deadline, request).await else {
+        return Ok(true);
+    };
+    let mut response = response?;
+
+    let mut pending = Vec::new();
+    loop {
+        let Some(chunk) = within(deadline, response.chunk()).await else {
+            // A row cut off mid-line is dropped rather than sent incomplete.
+            return Ok(true);
+        };
+        let Some(chunk) = chunk.map_err(|err| HousecatError::Response(err.to_string()))? else {
+            break;
+        };
+
+        pending.extend_from_slice(&chunk);
+        let Some(last_newline) = pending.iter().rposition(|byte| *byte == b'\n') else {
+            continue;
+        };
+
+        let complete: Vec<u8> = pending.drain(..=last_newline).collect();
+        for line in complete.split(|byte| *byte == b'\n') {
+            push_line(line, decoder, sender)?;
+        }
+    }
+
+    // The body need not end with a newline; its last line is still a row.
+    push_line(&pending, decoder, sender)?;
+    Ok(false)
+}
+
+fn push_line(
+    line: &[u8],
+    decoder: &mut RowDecoder,
+    sender: &mut ChunkSender<'_>,
+) -> Result<(), HousecatError> {
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return Ok(());
+    }
+
+    if let Some(row) = decoder.decode(line)? {
+        sender.rows.push(row);
+        if sender.rows.len() >= sender.batch_rows {
+            sender.send(decoder.columns.as_ref(), false, None)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs a query like `run_query` but sends its rows over `channel` in
+/// batches of `batchRows` as they arrive, instead of returning them all at
+/// once, so only one batch is held in memory at a time. Failures after the
+/// call starts, including HTTP errors, arrive as a final chunk with `error`
+/// set. Unlike `run_query`, a bare `SELECT` gets no default `LIMIT`, and
+/// `limit`, when given, is not capped. As with `run_query`, the query id
+/// is announced in a `query-started` event and `reportProgress` adds
+/// `query-stage` events; the display options (`columnar`,
+/// `stringifyNumbers`, `coerceDisplay`) do not apply.
+#[tauri::command]
+pub(crate) async fn run_query_stream(
+    app: AppHandle,
+    input: QueryInput,
+    channel: Channel<QueryChunk>,
+) -> Result<(), HousecatError> {
+    let query_id = match input.query_id.as_deref() {
+        Some(query_id) => require_value(query_id, "Query id")?.to_string(),
+        None => uuid::Uuid::new_v4().to_string(),
+    };
+    let batch_rows = input
+        .batch_rows
+        .unwrap_or(STREAM_BATCH_ROWS)
+        .clamp(1, MAX_STREAM_BATCH_ROWS) as usize;
+
+    let mut decoder = RowDecoder {
+        names: None,
+        columns: None,
+    };
+    let mut sender = ChunkSender {
+        channel: &channel,
+        columns_sent: false,
+        deadline_exceeded: false,
+        batch_rows,
+        rows: Vec::with_capacity(batch_rows),
+    };
+
+    progress::emit_query_started(&app, &query_id);
+    let poller = input
+        .report_progress
+        .unwrap_or(false)
+        .then(|| progress::spawn_progress_poller(app, input.connection.clone(), query_id.clone()));
+    let result = stream_rows(&input, &query_id, &mut decoder, &mut sender).await;
+    if let Some(poller) = poller {
+        poller.abort();
+    }
+
+    // The server stops at the same deadline, so its timeout error is just
+    // the other side noticing first.
+    let error = match result {
+        Ok(deadline_exceeded) => {
+            sender.deadline_exceeded = deadline_exceeded;
+            None
+        }
+        Err(err) if input.deadline().is_some() && err.is_timeout_exceeded() => {
+            sender.deadline_exceeded = true;
+            None
+        }
+        Err(err) => Some(err),
+    };
+    sender.send(decoder.columns.as_ref(), true, error)
+}
@@ -0,0 +1,33 @@
+use crate::error::HousecatError;
+use crate::statement::require_read_only;
+use crate::{fetch_rows, ClickHouseConnectionInput};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CanonicalSqlInput {
+    connection: ClickHouseConnectionInput,
+    query: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseExplainRow {
+    explain: String,
+}
+
+/// The query as the server rewrites it before planning, from
+/// `EXPLAIN SYNTAX`: aliases expanded, defaults applied and the text
+/// reformatted. Only read-only statements are accepted.
+#[tauri::command]
+pub(crate) async fn canonical_sql(input: CanonicalSqlInput) -> Result<String, HousecatError> {
+    let query = require_read_only(&input.query)?;
+
+    let rows: Vec<ClickHouseExplainRow> = fetch_rows(
+        &input.connection,
+        format!("EXPLAIN SYNTAX {query} FORMAT JSON"),
+    )
+    .await?;
+
+    let lines: Vec<String> = rows.into_iter().map(|row| row.explain).collect();
+    Ok(lines.join("\n"))
+}
@@ -0,0 +1,209 @@
+use crate::error::HousecatError;
+use crate::{
+    base_type, escape_identifier, fetch_rows, parse_preview_body, qualified_table,
+    quote_string_literal, require_value, run_clickhouse_query_with_params, value_literal,
+    ClickHouseConnectionInput, ColumnInfo,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const MIN_INTERVAL_MS: u64 = 500;
+const MAX_ROWS_PER_POLL: u32 = 1000;
+
+/// Poll tasks of active tails, keyed by tail id.
+#[derive(Debug, Default)]
+pub(crate) struct TailRegistry {
+    tails: Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+impl TailRegistry {
+    fn stop(&self, tail_id: &str) -> bool {
+        let handle = self
+            .tails
+            .lock()
+            .ok()
+            .and_then(|mut tails| tails.remove(tail_id));
+
+        match handle {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TailTableInput {
+    connection: ClickHouseConnectionInput,
+    schema: String,
+    table: String,
+    key_column: String,
+    interval_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseColumnType {
+    #[serde(rename = "type")]
+    data_type: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TailRows {
+    tail_id: String,
+    columns: Vec<ColumnInfo>,
+    rows: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TailError {
+    tail_id: String,
+    message: String,
+}
+
+/// Only keys that grow with every insert make sense to follow; this covers
+/// integers as well as `Date`/`DateTime` and their variants.
+fn is_monotonic_key_type(data_type: &str) -> bool {
+    let base = base_type(data_type);
+    base.starts_with("Int") || base.starts_with("UInt") || base.starts_with("Date")
+}
+
+async fn fetch_new_rows(
+    connection: &ClickHouseConnectionInput,
+    query: String,
+) -> Result<(Vec<ColumnInfo>, Vec<Value>), HousecatError> {
+    let body = run_clickhouse_query_with_params(
+        connection,
+        query,
+        &[("output_format_json_quote_64bit_integers", "0".to_string())],
+    )
+    .await?
+    .text()
+    .await
+    .map_err(|err| HousecatError::Response(err.to_string()))?;
+
+    let preview = parse_preview_body(&body);
+    Ok((preview.columns, preview.rows))
+}
+
+/// Follows an append-only table: every `interval_ms` it fetches rows whose
+/// `key_column` is greater than the last one seen and emits them as a
+/// `tail-rows` event. Returns the tail id to pass to `stop_tail`. A failed
+/// poll emits `tail-error` and ends the tail. Rows sharing a key value with
+/// the last row of a poll are skipped, so keys should be unique as well.
+#[tauri::command]
+pub(crate) async fn tail_table(
+    app: AppHandle,
+    input: TailTableInput,
+    registry: State<'_, TailRegistry>,
+) -> Result<String, HousecatError> {
+    let schema = require_value(&input.schema, "Schema")?.to_string();
+    let table = require_value(&input.table, "Table")?.to_string();
+    let key_column = require_value(&input.key_column, "Key column")?.to_string();
+    let interval = Duration::from_millis(input.interval_ms.unwrap_or(2000).max(MIN_INTERVAL_MS));
+
+    let columns: Vec<ClickHouseColumnType> = fetch_rows(
+        &input.connection,
+        format!(
+            "SELECT type FROM system.columns WHERE database = {} AND table = {} AND name = {} FORMAT JSON",
+            quote_string_literal(&schema),
+            quote_string_literal(&table),
+            quote_string_literal(&key_column)
+        ),
+    )
+    .await?;
+
+    let key_type = columns
+        .first()
+        .map(|column| column.data_type.clone())
+        .ok_or_else(|| {
+            HousecatError::invalid_input(format!("Column {key_column} does not exist"))
+        })?;
+
+    if !is_monotonic_key_type(&key_type) {
+        return Err(HousecatError::invalid_input(format!(
+            "Column {key_column} has type {key_type}; tailing needs an integer, Date or DateTime key"
+        )));
+    }
+
+    let target = qualified_table(&schema, &table);
+    let key = format!("`{}`", escape_identifier(&key_column));
+
+    // Start from the current maximum so only rows inserted from now on show.
+    let (_, rows) = fetch_new_rows(
+        &input.connection,
+        format!("SELECT max({key}) AS last_key FROM {target} FORMAT JSON"),
+    )
+    .await?;
+    let mut last_key = rows
+        .first()
+        .and_then(|row| row.get("last_key"))
+        .cloned()
+        .unwrap_or(Value::Null);
+
+    let tail_id = uuid::Uuid::new_v4().to_string();
+    let task_tail_id = tail_id.clone();
+    let connection = input.connection;
+
+    let handle = tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+
+            let query = format!(
+                "SELECT * FROM {target} WHERE {key} > {} ORDER BY {key} LIMIT {MAX_ROWS_PER_POLL} FORMAT JSON",
+                value_literal(&last_key, Some(&key_type))
+            );
+
+            match fetch_new_rows(&connection, query).await {
+                Ok((columns, rows)) => {
+                    let Some(newest) = rows.last().and_then(|row| row.get(&key_column)) else {
+                        continue;
+                    };
+                    last_key = newest.clone();
+
+                    let _ = app.emit(
+                        "tail-rows",
+                        TailRows {
+                            tail_id: task_tail_id.clone(),
+                            columns,
+                            rows,
+                        },
+                    );
+                }
+                Err(err) => {
+                    let _ = app.emit(
+                        "tail-error",
+                        TailError {
+                            tail_id: task_tail_id.clone(),
+                            message: err.to_string(),
+                        },
+                    );
+                    if let Ok(mut tails) = app.state::<TailRegistry>().tails.lock() {
+                        tails.remove(&task_tail_id);
+                    }
+                    return;
+                }
+            }
+        }
+    });
+
+    if let Ok(mut tails) = registry.tails.lock() {
+        tails.insert(tail_id.clone(), handle);
+    }
+
+    Ok(tail_id)
+}
+
+#[tauri::command]
+pub(crate) fn stop_tail(tail_id: String, registry: State<'_, TailRegistry>) -> bool {
+    registry.stop(&tail_id)
+}
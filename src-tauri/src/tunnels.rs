@@ -0,0 +1,421 @@
+use crate::error::HousecatError;
+use crate::{non_empty, ClickHouseConnectionInput};
+use russh::client::{self, Handle};
+use russh::keys::{PrivateKeyWithHashAlg, PublicKeyOrCertificate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const DEFAULT_SSH_PORT: u16 = 22;
+
+/// How to reach ClickHouse through an SSH bastion. Authenticates with the
+/// private key at `key_path` when given, otherwise with the keys offered
+/// by the running SSH agent.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SshTunnelConfig {
+    host: String,
+    port: Option<u16>,
+    username: String,
+    key_path: Option<String>,
+    /// Decrypts the key file. Never written to disk with a saved
+    /// connection.
+    #[serde(skip_serializing)]
+    key_passphrase: Option<String>,
+}
+
+impl SshTunnelConfig {
+    fn port(&self) -> u16 {
+        self.port.unwrap_or(DEFAULT_SSH_PORT)
+    }
+}
+
+/// Identifies a tunnel: which bastion, as whom, and where it forwards to.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct TunnelKey {
+    ssh_host: String,
+    ssh_port: u16,
+    ssh_username: String,
+    key_path: Option<String>,
+    target_host: String,
+    target_port: u16,
+}
+
+impl TunnelKey {
+    fn new(config: &SshTunnelConfig, input: &ClickHouseConnectionInput) -> Self {
+        Self {
+            ssh_host: config.host.trim().to_ascii_lowercase(),
+            ssh_port: config.port(),
+            ssh_username: config.username.trim().to_string(),
+            key_path: non_empty(config.key_path.as_deref()).map(str::to_string),
+            target_host: input.host.trim().to_ascii_lowercase(),
+            target_port: input.port,
+        }
+    }
+}
+
+/// An open SSH session and the local SOCKS5 listener forwarding through
+/// it. Dropping it stops the listener, which releases the last handle to
+/// the session and so disconnects it.
+struct Tunnel {
+    local_addr: SocketAddr,
+    session: Arc<Handle<KnownHosts>>,
+    listener: JoinHandle<()>,
+}
+
+impl Tunnel {
+    fn is_open(&self) -> bool {
+        !self.session.is_closed()
+    }
+}
+
+impl Drop for Tunnel {
+    fn drop(&mut self) {
+        self.listener.abort();
+    }
+}
+
+/// Process-wide for the same reason as the pooled clients: every query
+/// helper may need to go through a tunnel.
+static TUNNELS: Mutex<BTreeMap<TunnelKey, Tunnel>> = Mutex::new(BTreeMap::new());
+
+/// Held while a tunnel is being opened, so concurrent queries on a fresh
+/// connection share one SSH session instead of racing to open several.
+static OPENING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Accepts the server only if its key is listed for it in the user's
+/// `~/.ssh/known_hosts`, like `ssh` with `StrictHostKeyChecking=yes`.
+struct KnownHosts {
+    host: String,
+    port: u16,
+}
+
+impl client::Handler for KnownHosts {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &PublicKeyOrCertificate,
+    ) -> Result<bool, Self::Error> {
+        let PublicKeyOrCertificate::PublicKey { key, .. } = server_public_key else {
+            return Ok(false);
+        };
+        Ok(russh::keys::check_known_hosts(&self.host, self.port, key).unwrap_or(false))
+    }
+}
+
+fn tunnel_error(context: &str, err: impl std::fmt::Display) -> HousecatError {
+    HousecatError::Connection(format!("SSH tunnel {context}: {err}"))
+}
+
+/// The local proxy address for `input`'s tunnel, or `None` when it is not
+/// open.
+pub(crate) fn local_address(input: &ClickHouseConnectionInput) -> Option<SocketAddr> {
+    let config = input.ssh_tunnel.as_ref()?;
+    let tunnels = TUNNELS.lock().ok()?;
+    tunnels
+        .get(&TunnelKey::new(config, input))
+        .filter(|tunnel| tunnel.is_open())
+        .map(|tunnel| tunnel.local_addr)
+}
+
+/// The SSH host and port `input` tunnels through, if it has a tunnel.
+pub(crate) fn ssh_endpoint(input: &ClickHouseConnectionInput) -> Option<(&str, u16)> {
+    let config = input.ssh_tunnel.as_ref()?;
+    Some((config.host.trim(), config.port()))
+}
+
+/// Opens `input`'s tunnel unless it is already open, reopening it when
+/// the SSH session was lost. Connections without a tunnel pass through.
+pub(crate) async fn ensure(input: &ClickHouseConnectionInput) -> Result<(), HousecatError> {
+    let Some(config) = input.ssh_tunnel.as_ref() else {
+        return Ok(());
+    };
+    if local_address(input).is_some() {
+        return Ok(());
+    }
+
+    let _opening = OPENING.lock().await;
+    if local_address(input).is_some() {
+        return Ok(());
+    }
+
+    let key = TunnelKey::new(config, input);
+    let tunnel = open(config, &key, input.connect_timeout()).await?;
+    TUNNELS
+        .lock()
+        .map_err(|_| HousecatError::Client("tunnel registry is poisoned".to_string()))?
+        .insert(key, tunnel);
+    Ok(())
+}
+
+/// Shuts down `input`'s tunnel, if it has one.
+pub(crate) fn close(input: &ClickHouseConnectionInput) {
+    let Some(config) = input.ssh_tunnel.as_ref() else {
+        return;
+    };
+    if let Ok(mut tunnels) = TUNNELS.lock() {
+        tunnels.remove(&TunnelKey::new(config, input));
+    }
+}
+
+async fn open(
+    config: &SshTunnelConfig,
+    key: &TunnelKey,
+    connect_timeout_secs: u64,
+) -> Result<Tunnel, HousecatError> {
+    if key.ssh_host.is_empty() || key.ssh_username.is_empty() {
+        return Err(HousecatError::invalid_input(
+            "An SSH tunnel needs a host and a username",
+        ));
+    }
+
+    let handler = KnownHosts {
+        host: key.ssh_host.clone(),
+        port: key.ssh_port,
+    };
+    let connecting = client::connect(
+        Arc::new(client::Config::default()),
+        (key.ssh_host.as_str(), key.ssh_port),
+        handler,
+    );
+    let mut session =
+        match tokio::time::timeout(Duration::from_secs(connect_timeout_secs.max(1)), connecting)
+            .await
+        {
+            Ok(Ok(session)) => session,
+            Ok(Err(russh::Error::UnknownKey)) => {
+                return Err(HousecatError::Connection(format!(
+                "SSH host key of {}:{} is not in known_hosts; connect once with ssh to verify it",
+                key.ssh_host, key.ssh_port
+            )))
+            }
+            Ok(Err(err)) => return Err(tunnel_error("could not connect", err)),
+            Err(_) => {
+                return Err(HousecatError::Connection(format!(
+                    "SSH tunnel timed out connecting to {}:{}",
+                    key.ssh_host, key.ssh_port
+                )))
+            }
+        };
+
+    authenticate(&mut session, config, key).await?;
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .await
+        .map_err(|err| tunnel_error("could not listen locally", err))?;
+    let local_addr = listener
+        .local_addr()
+        .map_err(|err| tunnel_error("could not listen locally", err))?;
+    let session = Arc::new(session);
+    let listener = tauri::async_runtime::spawn(serve(
+        listener,
+        Arc::clone(&session),
+        key.target_host.clone(),
+        key.target_port,
+    ));
+
+    Ok(Tunnel {
+        local_addr,
+        session,
+        listener,
+    })
+}
+
+async fn authenticate(
+    session: &mut Handle<KnownHosts>,
+    config: &SshTunnelConfig,
+    key: &TunnelKey,
+) -> Result<(), HousecatError> {
+    let hash_alg = session
+        .best_supported_rsa_hash()
+        .await
+        .map_err(|err| tunnel_error("could not authenticate", err))?
+        .flatten();
+
+    let authenticated = match key.key_path.as_deref() {
+        Some(path) => {
+            let private_key = russh::keys::load_secret_key(path, config.key_passphrase.as_deref())
+                .map_err(|err| {
+                    HousecatError::invalid_input(format!("Could not load SSH key {path}: {err}"))
+                })?;
+            session
+                .authenticate_publickey(
+                    key.ssh_username.as_str(),
+                    PrivateKeyWithHashAlg::new(Arc::new(private_key), hash_alg),
+                )
+                .await
+                .map_err(|err| tunnel_error("could not authenticate", err))?
+                .success()
+        }
+        None => authenticate_with_agent(session, &key.ssh_username, hash_alg).await?,
+    };
+
+    if authenticated {
+        Ok(())
+    } else {
+        Err(HousecatError::Connection(format!(
+            "SSH server {} rejected the key for {}",
+            key.ssh_host, key.ssh_username
+        )))
+    }
+}
+
+#[cfg(unix)]
+async fn authenticate_with_agent(
+    session: &mut Handle<KnownHosts>,
+    username: &str,
+    hash_alg: Option<russh::keys::HashAlg>,
+) -> Result<bool, HousecatError> {
+    let mut agent = russh::keys::agent::client::AgentClient::connect_env()
+        .await
+        .map_err(|err| tunnel_error("could not reach the SSH agent", err))?;
+    let identities = agent
+        .request_identities()
+        .await
+        .map_err(|err| tunnel_error("could not list the SSH agent's keys", err))?;
+
+    for identity in identities {
+        let result = session
+            .authenticate_publickey_with(
+                username,
+                identity.public_key().into_owned(),
+                hash_alg,
+                &mut agent,
+            )
+            .await
+            .map_err(|err| tunnel_error("could not authenticate", err))?;
+        if result.success() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(not(unix))]
+async fn authenticate_with_agent(
+    _session: &mut Handle<KnownHosts>,
+    _username: &str,
+    _hash_alg: Option<russh::keys::HashAlg>,
+) -> Result<bool, HousecatError> {
+    Err(HousecatError::invalid_input(
+        "SSH agent authentication is not supported on this platform; set a key file",
+    ))
+}
+
+/// Accepts proxy connections until the tunnel is dropped, forwarding each
+/// through its own SSH channel.
+async fn serve(
+    listener: TcpListener,
+    session: Arc<Handle<KnownHosts>>,
+    target_host: String,
+    target_port: u16,
+) {
+    while let Ok((socket, _)) = listener.accept().await {
+        if session.is_closed() {
+            break;
+        }
+        let session = Arc::clone(&session);
+        let target_host = target_host.clone();
+        tauri::async_runtime::spawn(async move {
+            let _ = forward(socket, &session, &target_host, target_port).await;
+        });
+    }
+}
+
+/// SOCKS5 reply codes, RFC 1928 section 6.
+const SOCKS_SUCCEEDED: u8 = 0;
+const SOCKS_NOT_ALLOWED: u8 = 2;
+const SOCKS_HOST_UNREACHABLE: u8 = 4;
+const SOCKS_COMMAND_NOT_SUPPORTED: u8 = 7;
+const SOCKS_ADDRESS_NOT_SUPPORTED: u8 = 8;
+
+async fn reply(socket: &mut TcpStream, code: u8) -> std::io::Result<()> {
+    socket.write_all(&[5, code, 0, 1, 0, 0, 0, 0, 0, 0]).await
+}
+
+fn protocol_error(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// Serves one SOCKS5 `CONNECT`. The proxy is only there for the HTTP
+/// client, so it takes no credentials and reaches nothing but the
+/// ClickHouse server.
+async fn forward(
+    mut socket: TcpStream,
+    session: &Handle<KnownHosts>,
+    target_host: &str,
+    target_port: u16,
+) -> std::io::Result<()> {
+    let mut greeting = [0u8; 2];
+    socket.read_exact(&mut greeting).await?;
+    if greeting[0] != 5 {
+        return Err(protocol_error("not a SOCKS5 client"));
+    }
+    let mut methods = vec![0u8; usize::from(greeting[1])];
+    socket.read_exact(&mut methods).await?;
+    if !methods.contains(&0) {
+        socket.write_all(&[5, 0xff]).await?;
+        return Err(protocol_error("client requires authentication"));
+    }
+    socket.write_all(&[5, 0]).await?;
+
+    let mut request = [0u8; 4];
+    socket.read_exact(&mut request).await?;
+    let host = match request[3] {
+        1 => {
+            let mut address = [0u8; 4];
+            socket.read_exact(&mut address).await?;
+            Ipv4Addr::from(address).to_string()
+        }
+        3 => {
+            let mut length = [0u8; 1];
+            socket.read_exact(&mut length).await?;
+            let mut name = vec![0u8; usize::from(length[0])];
+            socket.read_exact(&mut name).await?;
+            String::from_utf8_lossy(&name).into_owned()
+        }
+        4 => {
+            let mut address = [0u8; 16];
+            socket.read_exact(&mut address).await?;
+            Ipv6Addr::from(address).to_string()
+        }
+        _ => {
+            reply(&mut socket, SOCKS_ADDRESS_NOT_SUPPORTED).await?;
+            return Err(protocol_error("unsupported address type"));
+        }
+    };
+    let mut port = [0u8; 2];
+    socket.read_exact(&mut port).await?;
+    let port = u16::from_be_bytes(port);
+
+    if request[1] != 1 {
+        reply(&mut socket, SOCKS_COMMAND_NOT_SUPPORTED).await?;
+        return Err(protocol_error("only CONNECT is supported"));
+    }
+    if !host.eq_ignore_ascii_case(target_host) || port != target_port {
+        reply(&mut socket, SOCKS_NOT_ALLOWED).await?;
+        return Err(protocol_error("destination is not the tunnel's target"));
+    }
+
+    let channel = match session
+        .channel_open_direct_tcpip(target_host, u32::from(target_port), "127.0.0.1", 0)
+        .await
+    {
+        Ok(channel) => channel,
+        Err(err) => {
+            reply(&mut socket, SOCKS_HOST_UNREACHABLE).await?;
+            return Err(std::io::Error::other(err));
+        }
+    };
+    reply(&mut socket, SOCKS_SUCCEEDED).await?;
+
+    let mut stream = channel.into_stream();
+    tokio::io::copy_bidirectional(&mut socket, &mut stream).await?;
+    Ok(())
+}
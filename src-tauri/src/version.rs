@@ -0,0 +1,88 @@
+use crate::error::HousecatError;
+use crate::{fetch_rows, require_value, ClickHouseConnectionInput};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MinVersionInput {
+    connection: ClickHouseConnectionInput,
+    min: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickHouseVersionRow {
+    version: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VersionCheck {
+    version: String,
+    minimum: String,
+    meets_minimum: bool,
+}
+
+/// Parses a ClickHouse version such as `23.8.1.2992` into its numeric
+/// components. Suffixes like `-lts` or `-testing` are ignored.
+pub(crate) fn parse_version(version: &str) -> Option<Vec<u32>> {
+    let numeric = version
+        .trim()
+        .trim_start_matches(['v', 'V'])
+        .split(['-', '+', ' '])
+        .next()?;
+
+    numeric
+        .split('.')
+        .map(|component| component.parse().ok())
+        .collect::<Option<Vec<u32>>>()
+        .filter(|components| !components.is_empty())
+}
+
+/// Compares versions component by component, treating missing trailing
+/// components as zero so `23.8` equals `23.8.0.0`.
+pub(crate) fn compare_versions(left: &[u32], right: &[u32]) -> Ordering {
+    let len = left.len().max(right.len());
+    (0..len)
+        .map(|index| {
+            let a = left.get(index).copied().unwrap_or(0);
+            let b = right.get(index).copied().unwrap_or(0);
+            a.cmp(&b)
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+pub(crate) async fn server_version(
+    connection: &ClickHouseConnectionInput,
+) -> Result<String, HousecatError> {
+    fetch_rows::<ClickHouseVersionRow>(
+        connection,
+        "SELECT version() AS version FORMAT JSON".to_string(),
+    )
+    .await?
+    .into_iter()
+    .next()
+    .map(|row| row.version)
+    .ok_or_else(|| HousecatError::Response("no version row returned".to_string()))
+}
+
+#[tauri::command]
+pub(crate) async fn require_min_version(
+    input: MinVersionInput,
+) -> Result<VersionCheck, HousecatError> {
+    let minimum = require_value(&input.min, "Minimum version")?;
+    let required = parse_version(minimum).ok_or_else(|| {
+        HousecatError::invalid_input(format!("`{minimum}` is not a version number"))
+    })?;
+
+    let version = server_version(&input.connection).await?;
+    let current = parse_version(&version)
+        .ok_or_else(|| HousecatError::Parse(format!("unrecognized server version `{version}`")))?;
+
+    Ok(VersionCheck {
+        meets_minimum: compare_versions(&current, &required).is_ge(),
+        version,
+        minimum: minimum.to_string(),
+    })
+}
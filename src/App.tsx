@@ -17,7 +17,7 @@ import { QueryEditor } from "./components/QueryEditor";
 import { ResultTable } from "./components/ResultTable";
 import { TabActions } from "./components/TabActions";
 import { closeTabWithActive, createQueryTab as makeQueryTab, createTableTab, duplicateTab } from "./state/viewerTabs";
-import type { SchemaTableItem, SchemaTables, TablePreview, ViewerTab } from "./types/viewer";
+import type { ColumnInfo, SchemaTableItem, SchemaTables, TablePreview, ViewerTab } from "./types/viewer";
 import "./App.css";
 
 type ConnectionForm = {
@@ -120,6 +120,18 @@ function persistQueryHistory(history: string[]) {
   localStorage.setItem(QUERY_HISTORY_KEY, JSON.stringify(history.slice(0, 100)));
 }
 
+function errorMessage(error: unknown, fallback: string): string {
+  if (typeof error === "string") {
+    return error;
+  }
+
+  if (error && typeof error === "object" && typeof (error as { message?: unknown }).message === "string") {
+    return (error as { message: string }).message;
+  }
+
+  return fallback;
+}
+
 function toConnectionPayload(connection: ConnectionForm): ConnectionPayload {
   return {
     host: connection.host.trim(),
@@ -193,9 +205,16 @@ function normalizeTablePreview(value: unknown): TablePreview {
     return { columns: [], rows: [] };
   }
 
-  const record = value as { columns?: unknown; rows?: unknown };
+  const record = value as { columns?: unknown; rows?: unknown; totalRows?: unknown };
   const columns = Array.isArray(record.columns)
-    ? record.columns.filter((column): column is string => typeof column === "string")
+    ? record.columns.filter((column): column is ColumnInfo => {
+        return (
+          Boolean(column) &&
+          typeof column === "object" &&
+          typeof (column as ColumnInfo).name === "string" &&
+          typeof (column as ColumnInfo).dataType === "string"
+        );
+      })
     : [];
 
   const rows = Array.isArray(record.rows)
@@ -204,7 +223,36 @@ function normalizeTablePreview(value: unknown): TablePreview {
       })
     : [];
 
-  return { columns, rows };
+  const totalRows = typeof record.totalRows === "number" ? record.totalRows : null;
+
+  return { columns, rows, totalRows };
+}
+
+// A multi-statement run comes back as one entry per statement. The grid
+// shows the last statement that returned rows; a failed statement, which
+// stops the run, is reported as the error.
+function normalizeQueryResult(value: unknown): { preview: TablePreview; error: string | null } {
+  if (!Array.isArray(value)) {
+    return { preview: normalizeTablePreview(value), error: null };
+  }
+
+  let preview: TablePreview = { columns: [], rows: [] };
+  let error: string | null = null;
+  value.forEach((entry, index) => {
+    if (!entry || typeof entry !== "object") {
+      return;
+    }
+
+    const record = entry as { output?: unknown; error?: unknown };
+    if (record.output) {
+      preview = normalizeTablePreview(record.output);
+    }
+    if (record.error && error === null) {
+      error = `Statement ${index + 1} of ${value.length} failed: ${errorMessage(record.error, "Query failed.")}`;
+    }
+  });
+
+  return { preview, error };
 }
 
 function getFirstTable(schemas: SchemaTables[]): { schema: string; table: string } | null {
@@ -433,7 +481,7 @@ function App() {
         ...tab,
         preview: { columns: [], rows: [] },
         isLoading: false,
-        error: typeof error === "string" ? error : "Failed to fetch table preview.",
+        error: errorMessage(error, "Failed to fetch table preview."),
       }));
     }
   }
@@ -475,13 +523,13 @@ function App() {
         },
       });
 
-      const normalized = normalizeTablePreview(result);
+      const { preview: normalized, error } = normalizeQueryResult(result);
 
       updateTab(activeTab.id, (tab) => ({
         ...tab,
         preview: normalized,
         isLoading: false,
-        error: null,
+        error,
         lastRunMs: Math.max(0, Math.round(performance.now() - started)),
         lastRowCount: normalized.rows.length,
       }));
@@ -490,7 +538,7 @@ function App() {
       updateTab(activeTab.id, (tab) => ({
         ...tab,
         isLoading: false,
-        error: typeof error === "string" ? error : "Query failed.",
+        error: errorMessage(error, "Query failed."),
       }));
     } finally {
       void refreshConnectionStatus();
@@ -592,7 +640,7 @@ function App() {
         updateTab(activeTab.id, (tab) => ({
           ...tab,
           isLoading: false,
-          error: typeof error === "string" ? error : "Sort query failed.",
+          error: errorMessage(error, "Sort query failed."),
         }));
       }
     }
@@ -685,7 +733,7 @@ function App() {
       setScreen("viewer");
     } catch (error) {
       setSchemaTables([]);
-      setConnectionError(typeof error === "string" ? error : "Failed to connect to ClickHouse.");
+      setConnectionError(errorMessage(error, "Failed to connect to ClickHouse."));
     } finally {
       setIsConnecting(false);
     }
@@ -713,7 +761,7 @@ function App() {
       });
       await refreshConnectionStatus();
     } catch (error) {
-      setConnectionError(typeof error === "string" ? error : "Refresh failed.");
+      setConnectionError(errorMessage(error, "Refresh failed."));
     } finally {
       setIsConnecting(false);
     }
@@ -78,10 +78,11 @@ export function ResultTable({
           <KumoTable className="data-table" layout="auto">
             <KumoTable.Header>
               <KumoTable.Row>
-                {activeTab.preview.columns.map((column) => (
+                {activeTab.preview.columns.map(({ name: column, dataType }) => (
                   <KumoTable.Head
                     key={column}
                     className="sortable-head"
+                    title={dataType}
                     onClick={() => {
                       onSortColumn(column);
                     }}
@@ -99,7 +100,7 @@ export function ResultTable({
             <KumoTable.Body>
               {activeTab.preview.rows.map((row, index) => (
                 <KumoTable.Row key={`row-${index}`}>
-                  {activeTab.preview.columns.map((column) => {
+                  {activeTab.preview.columns.map(({ name: column }) => {
                     const raw = formatCellValue(row[column]);
 
                     return (
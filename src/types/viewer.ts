@@ -8,9 +8,15 @@ export type SchemaTables = {
   tables: SchemaTableItem[];
 };
 
+export type ColumnInfo = {
+  name: string;
+  dataType: string;
+};
+
 export type TablePreview = {
-  columns: string[];
+  columns: ColumnInfo[];
   rows: Record<string, unknown>[];
+  totalRows?: number | null;
 };
 
 export type ViewerTabSort = {